@@ -0,0 +1,11 @@
+extern crate embed_resource;
+
+use embed_resource::CrateType;
+
+
+fn main() {
+    // None when not building for Windows, since shared-resources then has nothing to export
+    if let Some(artifact) = embed_resource::link_exported("shared-resources", CrateType::Bin) {
+        println!("cargo:warning=linking {}", artifact.path.display());
+    }
+}
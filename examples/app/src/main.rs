@@ -0,0 +1,7 @@
+extern crate shared_resources;
+
+
+fn main() {
+    // The version information from shared-resources/shared.rc shows up under this executable's Properties -> Details in Explorer
+    println!("Hello from {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+}
@@ -0,0 +1,9 @@
+extern crate embed_resource;
+
+use embed_resource::Options;
+
+
+fn main() {
+    // Exported, not linked: this crate is a library, so it's up to the executables depending on it
+    embed_resource::compile_with("shared.rc", Options::new().export(true)).unwrap();
+}
@@ -0,0 +1 @@
+//! Nothing but the resources compiled by the build script, for [`app`](../app) and any other dependents to link.
@@ -0,0 +1,80 @@
+use super::version_info::rc_quote;
+use super::CompileError;
+use std::ops::BitOr;
+
+
+/// The key of an accelerator for [`compile_accelerators()`](fn.compile_accelerators.html).
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AcceleratorKey<'a> {
+    /// A printable ASCII character, matched case-sensitively as typed (`ASCII`); can't be combined with modifiers
+    Char(char),
+    /// A virtual key (`VIRTKEY`): an uppercase letter or digit, like `"S"`, or a `VK_` constant, like `"VK_F5"`
+    Virtual(&'a str),
+}
+
+/// The modifiers to hold for an accelerator, combinable with `|`.
+#[derive(Debug, Copy, Clone, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Modifiers {
+    pub control: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers { control: false, shift: false, alt: false };
+    pub const CONTROL: Modifiers = Modifiers { control: true, shift: false, alt: false };
+    pub const SHIFT: Modifiers = Modifiers { control: false, shift: true, alt: false };
+    pub const ALT: Modifiers = Modifiers { control: false, shift: false, alt: true };
+}
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Modifiers) -> Modifiers {
+        Modifiers {
+            control: self.control || rhs.control,
+            shift: self.shift || rhs.shift,
+            alt: self.alt || rhs.alt,
+        }
+    }
+}
+
+
+/// Render an `ACCELERATORS` resource, validating the keys
+pub fn accelerators_script(id: u16, entries: &[(AcceleratorKey, u16, Modifiers)]) -> Result<String, CompileError> {
+    let mut rc = format!("{} ACCELERATORS\nBEGIN\n", id);
+    for &(key, command, modifiers) in entries {
+        let invalid = || CompileError::InvalidAccelerator(format!("{:?} with {:?}", key, modifiers));
+        let mut line = match key {
+            AcceleratorKey::Char(c) if (' '..='~').contains(&c) && modifiers == Modifiers::NONE => {
+                // As a character code, since quotes and backslashes aren't understood consistently in quoted events
+                format!("    {}, {}, ASCII", c as u32, command)
+            }
+            AcceleratorKey::Virtual(k) if k.len() == 1 && is_key_name(k, false) => {
+                format!("    {}, {}, VIRTKEY", rc_quote(k), command)
+            }
+            AcceleratorKey::Virtual(k) if k.starts_with("VK_") && k.len() > 3 && is_key_name(k, true) => {
+                format!("    {}, {}, VIRTKEY", k, command)
+            }
+            _ => return Err(invalid()),
+        };
+        if modifiers.control {
+            line.push_str(", CONTROL");
+        }
+        if modifiers.shift {
+            line.push_str(", SHIFT");
+        }
+        if modifiers.alt {
+            line.push_str(", ALT");
+        }
+        rc.push_str(&line);
+        rc.push('\n');
+    }
+    rc.push_str("END\n");
+    Ok(rc)
+}
+
+/// Whether the key is all uppercase letters and digits, and maybe underscores
+fn is_key_name(k: &str, underscores: bool) -> bool {
+    k.bytes().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || (underscores && c == b'_'))
+}
@@ -0,0 +1,32 @@
+/// A Windows target architecture.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Arch {
+    X86,
+    X64,
+    Arm,
+    Arm64,
+}
+
+
+/// Map a target triple to its architecture the same way this crate does, or `None` if it's not one Windows runs on.
+///
+/// # Examples
+///
+/// ```rust
+/// # use embed_resource::{Arch, detect_arch};
+/// assert_eq!(detect_arch("x86_64-pc-windows-msvc"), Some(Arch::X64));
+/// assert_eq!(detect_arch("i686-pc-windows-gnu"), Some(Arch::X86));
+/// assert_eq!(detect_arch("thumbv7a-pc-windows-msvc"), Some(Arch::Arm));
+/// assert_eq!(detect_arch("aarch64-pc-windows-msvc"), Some(Arch::Arm64));
+/// assert_eq!(detect_arch("riscv64gc-unknown-linux-gnu"), None);
+/// ```
+pub fn detect_arch(target: &str) -> Option<Arch> {
+    let arch = target.split('-').next().unwrap_or("");
+    match arch {
+        "x86_64" => Some(Arch::X64),
+        "i386" | "i586" | "i686" => Some(Arch::X86),
+        "aarch64" | "arm64ec" => Some(Arch::Arm64),
+        _ if arch.starts_with("thumbv7") || arch.starts_with("armv7") => Some(Arch::Arm),
+        _ => None,
+    }
+}
@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use super::{CompiledResource, DiscoveryStrategy};
+
+
+/// A successfully compiled resource.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Artifact {
+    /// The compiled resource, ready to be linked, as a library, or as an [object](struct.Options.html#method.object) if requested
+    pub path: PathBuf,
+    /// The directory containing `path`, to be added to the native library search path
+    pub link_search: PathBuf,
+    /// The name to link `path` as a library by, unless it was written to a specific [output path](struct.Options.html#method.output_path)
+    pub link_name: String,
+    /// The compiled resource as a `.res` file, if requested with [`Options::emit_res()`](struct.Options.html#method.emit_res),
+    /// or compiled on the way to an [object](struct.Options.html#method.object) with MSVC
+    pub res_path: Option<PathBuf>,
+    /// The copy of `path` made with [`Options::copy_artifact_to()`](struct.Options.html#method.copy_artifact_to), if requested
+    pub copy_path: Option<PathBuf>,
+    /// The copy of `res_path` made with [`Options::copy_artifact_to()`](struct.Options.html#method.copy_artifact_to), if both were requested
+    pub res_copy_path: Option<PathBuf>,
+    /// The resources in `path`, as `(type, name, language)`, if requested with [`Options::list_resources()`](struct.Options.html#method.list_resources)
+    pub resources: Vec<CompiledResource>,
+    /// The codepage the resource script was compiled in because it starts with a UTF-8 byte order mark,
+    /// if no [codepage](struct.Options.html#method.codepage) was specified
+    pub detected_codepage: Option<u16>,
+    /// The resource compiler's standard output, if captured with [`OutputMode::Captured`](enum.OutputMode.html)
+    pub stdout: String,
+    /// The resource compiler's standard error, if captured with [`OutputMode::Captured`](enum.OutputMode.html)
+    pub stderr: String,
+    /// The resource compiler that compiled it, or would have, if the output was [up to date](struct.Options.html#method.cache);
+    /// `None` for resources compiled elsewhere, as returned by [`link_exported()`](fn.link_exported.html)
+    pub compiler: Option<PathBuf>,
+    /// Where the resource compiler was found; `None` for resources compiled elsewhere, as returned by [`link_exported()`](fn.link_exported.html)
+    pub discovery_strategy: Option<DiscoveryStrategy>,
+    /// How long finding the resource compiler took
+    pub discovery_time: Duration,
+    /// How long compiling took, including retries and conversion to the output format; next to none if the output was [up to date](struct.Options.html#method.cache)
+    pub compile_time: Duration,
+}
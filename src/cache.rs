@@ -0,0 +1,144 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{PathBuf, Path};
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::time::Duration;
+use std::io;
+use super::{Options, script};
+use std::fmt;
+
+
+/// Whether compiling would reuse the previous output, as found by [`cache_status()`](fn.cache_status.html).
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CacheStatus {
+    /// The output is up to date and won't be recompiled
+    Hit,
+    /// The resource will be recompiled
+    Miss(CacheMiss),
+}
+
+/// Why a resource will be recompiled.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CacheMiss {
+    /// Caching isn't enabled with [`Options::cache()`](struct.Options.html#method.cache)
+    Disabled,
+    /// The resource script or one of its dependencies couldn't be read
+    UnreadableInput,
+    /// The output, or the requested `.res`, doesn't exist
+    MissingOutput,
+    /// The output wasn't produced with caching enabled
+    NotRecorded,
+    /// The options, compiler invocation, or inputs changed since the output was produced
+    Changed,
+    /// Recompilation was forced with [`Options::force()`](struct.Options.html#method.force) or `$EMBED_RESOURCE_FORCE`
+    Forced,
+    /// Another build sharing the output directory is compiling the same resource
+    Locked,
+}
+
+impl fmt::Display for CacheMiss {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            CacheMiss::Disabled => "caching is disabled",
+            CacheMiss::UnreadableInput => "an input couldn't be read",
+            CacheMiss::MissingOutput => "the output doesn't exist",
+            CacheMiss::NotRecorded => "the output has no recorded fingerprint",
+            CacheMiss::Changed => "the inputs changed",
+            CacheMiss::Forced => "recompilation was forced",
+            CacheMiss::Locked => "another build is compiling it",
+        })
+    }
+}
+
+
+
+/// Hash the options, the compiler invocation, and the contents of the resource script and all its dependencies.
+///
+/// `DefaultHasher` isn't stable across Rust releases, which, at worst, causes a spurious recompilation.
+pub fn fingerprint(resource: &Path, compiler: &Path, arguments: &[OsString], options: &Options) -> io::Result<String> {
+    let mut hasher = DefaultHasher::new();
+    options.hash(&mut hasher);
+    compiler.hash(&mut hasher);
+    arguments.hash(&mut hasher);
+
+    for file in Some(resource.to_path_buf()).into_iter().chain(script::dependencies(resource, &options.search_dirs().collect::<Vec<_>>())) {
+        file.hash(&mut hasher);
+        fs::read(&file)?.hash(&mut hasher);
+    }
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// Whether the output exists and was produced from inputs with the specified fingerprint
+pub fn status(out_file: &Path, fingerprint: &str) -> CacheStatus {
+    if !out_file.is_file() {
+        return CacheStatus::Miss(CacheMiss::MissingOutput);
+    }
+    match fs::read_to_string(sidecar(out_file, ".hash")) {
+        Ok(ref recorded) if recorded.trim() == fingerprint => CacheStatus::Hit,
+        Ok(_) => CacheStatus::Miss(CacheMiss::Changed),
+        Err(_) => CacheStatus::Miss(CacheMiss::NotRecorded),
+    }
+}
+
+/// Replace the recorded fingerprint, so a concurrent reader sees either the old or the new one
+pub fn store(out_file: &Path, fingerprint: &str) -> io::Result<()> {
+    let temp = sidecar(out_file, &format!(".hash.{}", std::process::id()));
+    fs::write(&temp, format!("{}\n", fingerprint))?;
+    if let Err(e) = fs::rename(&temp, sidecar(out_file, ".hash")) {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// How old a lock file has to be to be taken over, since a build killed while holding it leaves it behind
+const STALE_LOCK_AGE: Duration = Duration::from_secs(5 * 60);
+
+/// The lock on an output, released by removing the lock file when dropped
+pub struct Lock {
+    /// `None` where the lock file couldn't be created, and the output is used unlocked
+    path: Option<PathBuf>,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if let Some(ref path) = self.path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Take the lock guarding the output's cache status, compilation, and fingerprint until the returned one is dropped,
+/// by creating `{output}.lock`, which fails if it exists, or `None` if another build holds it,
+/// in which case the output may be recompiled, but the fingerprint mustn't be stored.
+///
+/// Lock files older than `STALE_LOCK_AGE` are taken over,
+/// and directories lock files can't be created in for other reasons are used unlocked.
+pub fn lock(out_file: &Path) -> Option<Lock> {
+    let path = sidecar(out_file, ".lock");
+    match create_lock_file(&path) {
+        Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            let stale = fs::metadata(&path).and_then(|m| m.modified()).ok().and_then(|t| t.elapsed().ok()).map(|age| age > STALE_LOCK_AGE);
+            if stale != Some(true) {
+                return None;
+            }
+            let _ = fs::remove_file(&path);
+            match create_lock_file(&path) {
+                Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => None,
+                ret => Some(Lock { path: ret.ok().map(|_| path) }),
+            }
+        }
+        ret => Some(Lock { path: ret.ok().map(|_| path) }),
+    }
+}
+
+fn create_lock_file(path: &Path) -> io::Result<()> {
+    OpenOptions::new().write(true).create_new(true).open(path).map(|_| ())
+}
+
+fn sidecar(out_file: &Path, extension: &str) -> PathBuf {
+    let mut ret = out_file.as_os_str().to_os_string();
+    ret.push(extension);
+    PathBuf::from(ret)
+}
@@ -0,0 +1,180 @@
+use std::env;
+
+
+/// Whether the `cfg` predicate holds for the target being built for, per the `$CARGO_CFG_*` variables Cargo sets for build scripts,
+/// or `None` if it's malformed
+pub fn matches(predicate: &str) -> Option<bool> {
+    let mut parser = Parser { rest: predicate };
+    let ret = parser.predicate()?;
+    if parser.rest.trim().is_empty() {
+        Some(ret)
+    } else {
+        None
+    }
+}
+
+
+struct Parser<'p> {
+    rest: &'p str,
+}
+
+impl<'p> Parser<'p> {
+    fn predicate(&mut self) -> Option<bool> {
+        let name = self.identifier()?;
+        match name {
+            "all" | "any" | "not" => {
+                let values = self.list()?;
+                match name {
+                    "all" => Some(values.iter().all(|&v| v)),
+                    "any" => Some(values.iter().any(|&v| v)),
+                    _ if values.len() == 1 => Some(!values[0]),
+                    _ => None,
+                }
+            }
+            _ => {
+                if !self.eat('=') {
+                    return Some(!cfg_values(name).is_empty());
+                }
+                let value = self.string()?;
+                Some(cfg_values(name).iter().any(|v| v == value))
+            }
+        }
+    }
+
+    /// `(predicate, ...)`, with an optional trailing comma
+    fn list(&mut self) -> Option<Vec<bool>> {
+        if !self.eat('(') {
+            return None;
+        }
+        let mut ret = vec![];
+        while !self.eat(')') {
+            ret.push(self.predicate()?);
+            if !self.eat(',') && !self.rest.trim_start().starts_with(')') {
+                return None;
+            }
+        }
+        Some(ret)
+    }
+
+    fn identifier(&mut self) -> Option<&'p str> {
+        let rest = self.rest.trim_start();
+        let end = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        if end == 0 || rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        self.rest = &rest[end..];
+        Some(&rest[..end])
+    }
+
+    fn string(&mut self) -> Option<&'p str> {
+        let rest = self.rest.trim_start();
+        if !rest.starts_with('"') {
+            return None;
+        }
+        let rest = &rest[1..];
+        let end = rest.find('"')?;
+        self.rest = &rest[end + 1..];
+        Some(&rest[..end])
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let rest = self.rest.trim_start();
+        if rest.starts_with(c) {
+            self.rest = &rest[c.len_utf8()..];
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// The values of the configuration option: `$CARGO_CFG_{NAME}`, comma-separated, an empty string if it's set without one,
+/// or, for `feature`, the enabled features, from `$CARGO_FEATURE_{FEATURE}`
+fn cfg_values(name: &str) -> Vec<String> {
+    if name == "feature" {
+        return env::vars()
+            .filter(|(var, _)| var.starts_with("CARGO_FEATURE_"))
+            .map(|(var, _)| var["CARGO_FEATURE_".len()..].to_lowercase().replace('_', "-"))
+            .flat_map(|f| vec![f.clone(), f.replace('-', "_")])
+            .collect();
+    }
+    match env::var(format!("CARGO_CFG_{}", name.to_uppercase())) {
+        Ok(values) => values.split(',').map(str::to_string).collect(),
+        Err(_) => vec![],
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::matches;
+    use std::env;
+
+    // Tests run in parallel in one process, so each sets only variables of its own
+
+    #[test]
+    fn options_and_values() {
+        env::set_var("CARGO_CFG_EMBED_RESOURCE_TEST_OS", "windows");
+        env::set_var("CARGO_CFG_EMBED_RESOURCE_TEST_FAMILY", "windows,unix");
+        env::set_var("CARGO_CFG_EMBED_RESOURCE_TEST_FLAG", "");
+
+        assert_eq!(matches("embed_resource_test_os = \"windows\""), Some(true));
+        assert_eq!(matches("embed_resource_test_os=\"linux\""), Some(false));
+        assert_eq!(matches("embed_resource_test_family = \"unix\""), Some(true));
+        assert_eq!(matches("embed_resource_test_flag"), Some(true));
+        assert_eq!(matches("embed_resource_test_unset"), Some(false));
+        assert_eq!(matches("embed_resource_test_unset = \"windows\""), Some(false));
+        assert_eq!(matches("all(embed_resource_test_flag, not(embed_resource_test_os = \"linux\"))"), Some(true));
+        assert_eq!(matches("any(embed_resource_test_unset, embed_resource_test_os = \"linux\")"), Some(false));
+        assert_eq!(matches("all()"), Some(true));
+        assert_eq!(matches("any()"), Some(false));
+    }
+
+    #[test]
+    fn trailing_commas() {
+        env::set_var("CARGO_CFG_EMBED_RESOURCE_TEST_COMMA", "yes");
+
+        assert_eq!(matches("all(embed_resource_test_comma,)"), Some(true));
+        assert_eq!(matches("any(embed_resource_test_unset, embed_resource_test_comma ,  )"), Some(true));
+        assert_eq!(matches("not(embed_resource_test_comma,)"), Some(false));
+        assert_eq!(matches("all(,)"), None);
+        assert_eq!(matches("all(embed_resource_test_comma,,)"), None);
+    }
+
+    #[test]
+    fn not_arity() {
+        assert_eq!(matches("not(embed_resource_test_unset)"), Some(true));
+        assert_eq!(matches("not()"), None);
+        assert_eq!(matches("not(embed_resource_test_unset, embed_resource_test_unset)"), None);
+    }
+
+    #[test]
+    fn feature_names() {
+        env::set_var("CARGO_FEATURE_EMBED_RESOURCE_TEST_DARK_MODE", "1");
+
+        assert_eq!(matches("feature = \"embed-resource-test-dark-mode\""), Some(true));
+        assert_eq!(matches("feature = \"embed_resource_test_dark_mode\""), Some(true));
+        assert_eq!(matches("feature = \"EMBED_RESOURCE_TEST_DARK_MODE\""), Some(false));
+        assert_eq!(matches("feature = \"embed-resource-test-light-mode\""), Some(false));
+    }
+
+    #[test]
+    fn malformed() {
+        for predicate in &["",
+                           "   ",
+                           "1abc",
+                           "unix unix",
+                           "unix = windows",
+                           "unix = \"windows",
+                           "unix =",
+                           "all",
+                           "all(unix",
+                           "all(unix windows)",
+                           "any(unix))",
+                           "not unix",
+                           "unix, windows",
+                           "(unix)"] {
+            assert_eq!(matches(predicate), None, "{:?}", predicate);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use super::{ResourceType, ResourceId, VersionInfo, Manifest};
+use std::path::{PathBuf, Path};
+
+
+/// A section of a [`CombinedScript`](struct.CombinedScript.html), generated in the [specified order](struct.CombinedScript.html#method.order).
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ScriptSection {
+    /// The [manifest](struct.CombinedScript.html#method.manifest)
+    Manifest,
+    /// The [version information](struct.CombinedScript.html#method.version_info)
+    VersionInfo,
+    /// The [entries](struct.CombinedScript.html#method.entry) of type `ResourceType::Icon`
+    Icons,
+    /// The other entries
+    Other,
+}
+
+impl ScriptSection {
+    /// The default order: manifest, version information, icons, then the rest
+    pub const DEFAULT_ORDER: [ScriptSection; 4] = [ScriptSection::Manifest, ScriptSection::VersionInfo, ScriptSection::Icons, ScriptSection::Other];
+}
+
+
+/// A manifest, version information, and resources read from files, generated into one resource script,
+/// to be compiled with [`compile_combined()`](fn.compile_combined.html).
+///
+/// The sections are generated in the [specified order](#method.order), which can matter:
+///   * a [`VersionInfo::language()`](struct.VersionInfo.html#method.language) `LANGUAGE` statement
+///     also applies to the sections after the version information,
+///   * the `.res` file stores the resources in script order, which is the order tools reading it directly see them in,
+///     unlike in the linked executable, whose resource directory is sorted,
+///   * and `RC.EXE` numbers the individual images of icon groups in script order,
+///     so moving the icons changes the IDs of the `RT_ICON` resources.
+#[derive(Debug, Clone)]
+pub struct CombinedScript<'a> {
+    pub(crate) manifest: Option<Manifest>,
+    pub(crate) version_info: Option<VersionInfo>,
+    pub(crate) entries: Vec<(ResourceType<'a>, ResourceId, PathBuf)>,
+    order: Vec<ScriptSection>,
+}
+
+impl<'a> CombinedScript<'a> {
+    /// An empty script, generating the sections in the [default order](enum.ScriptSection.html#associatedconstant.DEFAULT_ORDER).
+    pub fn new() -> CombinedScript<'a> {
+        CombinedScript {
+            manifest: None,
+            version_info: None,
+            entries: vec![],
+            order: ScriptSection::DEFAULT_ORDER.to_vec(),
+        }
+    }
+
+    /// Embed the manifest, with the ID appropriate for [`Options::crate_type()`](struct.Options.html#method.crate_type),
+    /// like [`compile_manifest_with()`](fn.compile_manifest_with.html), replacing the previous one, if any.
+    pub fn manifest(mut self, manifest: Manifest) -> CombinedScript<'a> {
+        self.manifest = Some(manifest);
+        self
+    }
+
+    /// Embed the version information, replacing the previous one, if any.
+    pub fn version_info(mut self, version_info: VersionInfo) -> CombinedScript<'a> {
+        self.version_info = Some(version_info);
+        self
+    }
+
+    /// Embed the file as a resource of the specified type, like [`compile_resource_entries()`](fn.compile_resource_entries.html);
+    /// entries in the same section are generated in the order they're added in.
+    pub fn entry<P: AsRef<Path>>(mut self, resource_type: ResourceType<'a>, id: ResourceId, path: P) -> CombinedScript<'a> {
+        self.entries.push((resource_type, id, path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Generate the specified sections first, in the specified order, then the others in the default order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use embed_resource::{CombinedScript, ScriptSection};
+    /// let script = CombinedScript::new().order(&[ScriptSection::Icons]);
+    /// assert_eq!(script.sections(), [ScriptSection::Icons, ScriptSection::Manifest, ScriptSection::VersionInfo, ScriptSection::Other]);
+    /// ```
+    pub fn order(mut self, order: &[ScriptSection]) -> CombinedScript<'a> {
+        self.order = vec![];
+        for &section in order.iter().chain(ScriptSection::DEFAULT_ORDER.iter()) {
+            if !self.order.contains(&section) {
+                self.order.push(section);
+            }
+        }
+        self
+    }
+
+    /// The order the sections are generated in.
+    pub fn sections(&self) -> &[ScriptSection] {
+        &self.order
+    }
+}
+
+impl<'a> Default for CombinedScript<'a> {
+    fn default() -> CombinedScript<'a> {
+        CombinedScript::new()
+    }
+}
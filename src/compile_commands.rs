@@ -0,0 +1,69 @@
+use std::io::{self, ErrorKind as IoErrorKind};
+use std::ffi::OsString;
+use std::path::{PathBuf, Path};
+use std::{env, fs};
+
+
+/// Add the compiler invocation to the `compile_commands.json`-style database at `database`,
+/// replacing previous records for the same file in the same directory.
+///
+/// Records are written one per line, which is also how they're recognised when updating the database.
+/// The invocation is recorded as an `arguments` array rather than a `command` string,
+/// so that paths with spaces, like the usual `C:\Program Files (x86)\Windows Kits`, needn't be quoted.
+pub fn record(database: &Path, directory: &Path, file: &str, compiler: &Path, arguments: &[OsString], output: &str) -> io::Result<()> {
+    let key = format!("{{\"directory\": {}, \"file\": {},", json_string(&directory.to_string_lossy()), json_string(file));
+
+    let mut record = key.clone();
+    record.push_str(" \"arguments\": [");
+    record.push_str(&json_string(&resolve_in_path(compiler).to_string_lossy()));
+    for arg in arguments {
+        record.push_str(", ");
+        record.push_str(&json_string(&arg.to_string_lossy()));
+    }
+    record.push_str("], \"output\": ");
+    record.push_str(&json_string(output));
+    record.push('}');
+
+    let existing = match fs::read_to_string(database) {
+        Ok(existing) => existing,
+        Err(ref e) if e.kind() == IoErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let mut records: Vec<_> = existing.lines()
+        .map(|l| l.trim().trim_end_matches(','))
+        .filter(|l| l.starts_with('{') && !l.starts_with(&key))
+        .collect();
+    records.push(&record);
+
+    fs::write(database, format!("[\n{}\n]\n", records.join(",\n")))
+}
+
+/// Find a bare compiler name in `$PATH` like the OS would, leaving paths and unfound names as-is
+pub fn resolve_in_path(compiler: &Path) -> PathBuf {
+    if compiler.components().count() != 1 {
+        return compiler.to_path_buf();
+    }
+
+    env::var_os("PATH")
+        .and_then(|path| {
+            env::split_paths(&path)
+                .flat_map(|dir| vec![dir.join(compiler), dir.join(compiler).with_extension("exe")])
+                .find(|candidate| candidate.is_file())
+        })
+        .unwrap_or_else(|| compiler.to_path_buf())
+}
+
+fn json_string(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len() + 2);
+    ret.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            c if (c as u32) < 0x20 => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
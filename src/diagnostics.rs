@@ -0,0 +1,162 @@
+use std::fmt;
+
+
+/// How bad a [`Diagnostic`](struct.Diagnostic.html) is.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+
+/// A single warning or error reported by the resource compiler.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// E.g. `RC4005`, for `RC.EXE`
+    pub code: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref file) = self.file {
+            f.write_str(file)?;
+            if let Some(line) = self.line {
+                write!(f, ":{}", line)?;
+            }
+            f.write_str(": ")?;
+        }
+        write!(f, "{}", self.severity)?;
+        if let Some(ref code) = self.code {
+            write!(f, " {}", code)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+
+/// Extract the diagnostics from the resource compiler's output.
+///
+/// Both `RC.EXE`'s `file(line) : warning RC1234 : message` format and `windres`'/the preprocessor's
+/// `file:line[:column]: warning: message` format are recognised; all other lines are ignored.
+///
+/// # Examples
+///
+/// ```rust
+/// # use embed_resource::{parse_diagnostics, Severity};
+/// let diags = parse_diagnostics("Microsoft (R) Windows (R) Resource Compiler Version 10.0.10011.16384\n\
+///                                app.rc(12) : warning RC4005 : 'VER' : redefinition\n");
+/// assert_eq!(diags.len(), 1);
+/// assert_eq!(diags[0].severity, Severity::Warning);
+/// assert_eq!(diags[0].code.as_ref().map(String::as_str), Some("RC4005"));
+/// assert_eq!(diags[0].line, Some(12));
+///
+/// let diags = parse_diagnostics("app.rc:3:10: fatal error: resource.h: No such file or directory\n");
+/// assert_eq!(diags[0].severity, Severity::Error);
+/// assert_eq!(diags[0].file.as_ref().map(String::as_str), Some("app.rc"));
+/// ```
+pub fn parse_diagnostics(output: &str) -> Vec<Diagnostic> {
+    output.lines().filter_map(|l| parse_msvc_line(l).or_else(|| parse_gnu_line(l))).collect()
+}
+
+
+const MSVC_SEVERITIES: &[(&str, Severity)] = &[("fatal error ", Severity::Error), ("error ", Severity::Error), ("warning ", Severity::Warning)];
+const GNU_SEVERITIES: &[(&str, Severity)] = &[(": fatal error: ", Severity::Error),
+                                              (": error: ", Severity::Error),
+                                              (": warning: ", Severity::Warning),
+                                              (": note: ", Severity::Note)];
+
+fn parse_msvc_line(line: &str) -> Option<Diagnostic> {
+    let line = line.trim();
+    let (location, rest) = match line.find(" : ").filter(|&i| MSVC_SEVERITIES.iter().any(|s| line[i + 3..].starts_with(s.0))) {
+        Some(i) => (Some(&line[..i]), &line[i + 3..]),
+        None => (None, line),
+    };
+
+    let &(prefix, severity) = MSVC_SEVERITIES.iter().find(|s| rest.starts_with(s.0))?;
+    let rest = &rest[prefix.len()..];
+
+    let code_len = rest.find(&[' ', ':'][..]).unwrap_or(rest.len());
+    let code = &rest[..code_len];
+    let digits_at = code.find(|c: char| c.is_ascii_digit())?;
+    if digits_at == 0 || !code[..digits_at].chars().all(|c| c.is_ascii_alphabetic()) || !code[digits_at..].chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (file, line) = match location {
+        Some(location) if location.ends_with(')') => {
+            let open = location.rfind('(')?;
+            let line = location[open + 1..location.len() - 1].split(',').next().and_then(|l| l.trim().parse().ok());
+            (Some(location[..open].trim().to_string()), line)
+        }
+        _ => (None, None),
+    };
+
+    Some(Diagnostic {
+        severity,
+        code: Some(code.to_string()),
+        file,
+        line,
+        message: rest[code_len..].trim_start_matches(&[' ', ':'][..]).trim().to_string(),
+    })
+}
+
+fn parse_gnu_line(line: &str) -> Option<Diagnostic> {
+    let line = line.trim();
+    if let Some((i, &(marker, severity))) = GNU_SEVERITIES.iter().filter_map(|s| line.find(s.0).map(|i| (i, s))).min_by_key(|&(i, _)| i) {
+        let (file, line_no) = split_gnu_location(&line[..i]);
+        Some(Diagnostic {
+            severity,
+            code: None,
+            file: Some(file.to_string()),
+            line: line_no,
+            message: line[i + marker.len()..].trim().to_string(),
+        })
+    } else if let Some(i) = line.find("windres: ") {
+        // windres' own errors have no severity: "windres: app.rc:12: syntax error"
+        let rest = &line[i + "windres: ".len()..];
+        let (file, line_no, message) = match rest.find(": ").map(|i| (i, split_gnu_location(&rest[..i]))) {
+            Some((i, (file, Some(line_no)))) => (Some(file.to_string()), Some(line_no), &rest[i + 2..]),
+            _ => (None, None, rest),
+        };
+        Some(Diagnostic {
+            severity: Severity::Error,
+            code: None,
+            file,
+            line: line_no,
+            message: message.trim().to_string(),
+        })
+    } else {
+        None
+    }
+}
+
+/// `file:line:column`, `file:line`, or `file`, where `file` may contain colons
+fn split_gnu_location(location: &str) -> (&str, Option<u32>) {
+    let mut location = location;
+    let mut line = None;
+    for _ in 0..2 {
+        match location.rfind(':').filter(|&i| !location[i + 1..].is_empty() && location[i + 1..].chars().all(|c| c.is_ascii_digit())) {
+            Some(i) => {
+                line = location[i + 1..].parse().ok();
+                location = &location[..i];
+            }
+            None => break,
+        }
+    }
+    (location, line)
+}
@@ -0,0 +1,206 @@
+use super::version_info::rc_quote;
+use super::CompileError;
+
+
+/// The window class of a [`DialogControl`](struct.DialogControl.html).
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ControlClass {
+    /// A push button (`BUTTON` with `BS_PUSHBUTTON | WS_TABSTOP`)
+    Button,
+    /// A left-aligned label (`STATIC` with `SS_LEFT`)
+    Static,
+    /// A bordered text box (`EDIT` with `ES_LEFT | WS_BORDER | WS_TABSTOP`)
+    Edit,
+    /// A control of another registered class, like `"msctls_progress32"`, with no default styles
+    Custom(String),
+}
+
+impl ControlClass {
+    fn name(&self) -> &str {
+        match *self {
+            ControlClass::Button => "BUTTON",
+            ControlClass::Static => "STATIC",
+            ControlClass::Edit => "EDIT",
+            ControlClass::Custom(ref name) => name,
+        }
+    }
+
+    fn default_styles(&self) -> &'static [&'static str] {
+        match *self {
+            ControlClass::Button => &["BS_PUSHBUTTON", "WS_TABSTOP"],
+            ControlClass::Static => &["SS_LEFT"],
+            ControlClass::Edit => &["ES_LEFT", "WS_BORDER", "WS_TABSTOP"],
+            ControlClass::Custom(_) => &[],
+        }
+    }
+}
+
+
+/// A control in a [`DialogSpec`](struct.DialogSpec.html), always visible.
+///
+/// Positions and sizes are in dialog units, relative to the dialog's client area.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct DialogControl {
+    class: ControlClass,
+    id: u16,
+    text: String,
+    rect: (u16, u16, u16, u16),
+    styles: Vec<String>,
+}
+
+impl DialogControl {
+    /// A control of the specified class, with the specified ID, for `GetDlgItem()` and `WM_COMMAND`,
+    /// text, and `(x, y, width, height)`.
+    ///
+    /// Labels customarily have ID `0xFFFF` (`IDC_STATIC`, or -1).
+    pub fn new<T: Into<String>>(class: ControlClass, id: u16, text: T, rect: (u16, u16, u16, u16)) -> DialogControl {
+        DialogControl {
+            class,
+            id,
+            text: text.into(),
+            rect,
+            styles: vec![],
+        }
+    }
+
+    /// Add the style constant, like `"BS_DEFPUSHBUTTON"` or `"ES_PASSWORD"`, to the class' default styles.
+    pub fn style<S: Into<String>>(mut self, style: S) -> DialogControl {
+        self.styles.push(style.into());
+        self
+    }
+}
+
+
+/// A `DIALOGEX` resource, a dialog box template for `DialogBox()` & co., built up and rendered to a resource script.
+///
+/// This covers simple dialogs made of the common controls; anything more elaborate is best written as a resource script.
+///
+/// # Examples
+///
+/// ```rust
+/// # use embed_resource::{ControlClass, DialogControl, DialogSpec};
+/// let rc = DialogSpec::new(186, 60)
+///     .caption("About Checksums")
+///     .control(DialogControl::new(ControlClass::Static, 0xFFFF, "Checksums 1.0", (7, 7, 172, 8)))
+///     .control(DialogControl::new(ControlClass::Button, 1, "OK", (129, 39, 50, 14)).style("BS_DEFPUSHBUTTON"))
+///     .to_rc(101)
+///     .unwrap();
+/// assert!(rc.starts_with("101 DIALOGEX 0, 0, 186, 60\n"));
+/// assert!(rc.contains(r#"CAPTION "About Checksums""#));
+/// assert!(rc.contains(r#"CONTROL "OK", 1, "BUTTON", WS_CHILD | WS_VISIBLE | BS_PUSHBUTTON | WS_TABSTOP | BS_DEFPUSHBUTTON, 129, 39, 50, 14"#));
+///
+/// // Out of the dialog's bounds
+/// assert!(DialogSpec::new(100, 50).control(DialogControl::new(ControlClass::Edit, 2, "", (60, 7, 50, 14))).to_rc(102).is_err());
+/// ```
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct DialogSpec {
+    size: (u16, u16),
+    caption: Option<String>,
+    font: (u16, String),
+    styles: Vec<String>,
+    controls: Vec<DialogControl>,
+}
+
+impl DialogSpec {
+    /// An empty modal dialog of the specified width and height, in dialog units, in 8-point `MS Shell Dlg`.
+    pub fn new(width: u16, height: u16) -> DialogSpec {
+        DialogSpec {
+            size: (width, height),
+            caption: None,
+            font: (8, "MS Shell Dlg".to_string()),
+            styles: vec![],
+            controls: vec![],
+        }
+    }
+
+    /// Set the title bar text, adding a title bar (`WS_CAPTION`) and a close button (`WS_SYSMENU`).
+    pub fn caption<C: Into<String>>(mut self, caption: C) -> DialogSpec {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    /// Set the font, which also determines the size of a dialog unit.
+    pub fn font<F: Into<String>>(mut self, point_size: u16, typeface: F) -> DialogSpec {
+        self.font = (point_size, typeface.into());
+        self
+    }
+
+    /// Add the style constant, like `"DS_CENTER"`, to the default `DS_SETFONT | DS_MODALFRAME | WS_POPUP`.
+    pub fn style<S: Into<String>>(mut self, style: S) -> DialogSpec {
+        self.styles.push(style.into());
+        self
+    }
+
+    /// Add a control, after the previously added ones in tab order.
+    pub fn control(mut self, control: DialogControl) -> DialogSpec {
+        self.controls.push(control);
+        self
+    }
+
+    /// Render the dialog with the specified ID to a resource script, which needs `windows.h` for the style constants.
+    ///
+    /// The sizes must be non-zero, all coordinates at most 32767, the controls within the dialog,
+    /// the style constants identifiers, and the custom class names non-empty and unquoted.
+    pub fn to_rc(&self, id: u16) -> Result<String, CompileError> {
+        let invalid = |what: String| Err(CompileError::InvalidDialog(what));
+        let (width, height) = self.size;
+        if !is_valid_size(width) || !is_valid_size(height) {
+            return invalid(format!("dialog size {}x{}", width, height));
+        }
+        if let Some(style) = self.styles.iter().chain(self.controls.iter().flat_map(|c| &c.styles)).find(|s| !is_constant(s)) {
+            return invalid(format!("style \"{}\"", style));
+        }
+
+        let mut styles = vec!["DS_SETFONT", "DS_MODALFRAME", "WS_POPUP"];
+        if self.caption.is_some() {
+            styles.extend(&["WS_CAPTION", "WS_SYSMENU"]);
+        }
+        styles.extend(self.styles.iter().map(|s| &s[..]));
+
+        let mut rc = format!("{} DIALOGEX 0, 0, {}, {}\n", id, width, height);
+        rc.push_str(&format!("STYLE {}\n", styles.join(" | ")));
+        if let Some(ref caption) = self.caption {
+            rc.push_str(&format!("CAPTION {}\n", rc_quote(caption)));
+        }
+        rc.push_str(&format!("FONT {}, {}\n", self.font.0, rc_quote(&self.font.1)));
+        rc.push_str("BEGIN\n");
+        for control in &self.controls {
+            let class = control.class.name();
+            if class.is_empty() || class.contains('"') || class.chars().any(char::is_control) {
+                return invalid(format!("control class \"{}\"", class));
+            }
+            let (x, y, w, h) = control.rect;
+            if !is_valid_size(w) || !is_valid_size(h) || x as u32 + w as u32 > width as u32 || y as u32 + h as u32 > height as u32 {
+                return invalid(format!("control {} at ({}, {}) sized {}x{}, outside of the {}x{} dialog", control.id, x, y, w, h, width, height));
+            }
+
+            let styles: Vec<&str> = ["WS_CHILD", "WS_VISIBLE"]
+                .iter()
+                .chain(control.class.default_styles())
+                .cloned()
+                .chain(control.styles.iter().map(|s| &s[..]))
+                .collect();
+            rc.push_str(&format!("    CONTROL {}, {}, {}, {}, {}, {}, {}, {}\n",
+                                 rc_quote(&control.text),
+                                 control.id,
+                                 rc_quote(class),
+                                 styles.join(" | "),
+                                 x,
+                                 y,
+                                 w,
+                                 h));
+        }
+        rc.push_str("END\n");
+        Ok(rc)
+    }
+}
+
+
+/// Dialog templates store coordinates as signed 16-bit numbers
+fn is_valid_size(size: u16) -> bool {
+    size != 0 && size <= std::i16::MAX as u16
+}
+
+fn is_constant(style: &str) -> bool {
+    style.chars().next().map(|c| c.is_ascii_alphabetic() || c == '_').unwrap_or(false) && style.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
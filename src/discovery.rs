@@ -0,0 +1,98 @@
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::atomic::{AtomicBool, AtomicPtr};
+use std::sync::RwLock;
+use std::path::Path;
+use std::{fmt, ptr};
+use super::lazy;
+
+
+/// Where the resource compiler was found, as returned in [`Artifact::discovery_strategy`](struct.Artifact.html#structfield.discovery_strategy).
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiscoveryStrategy {
+    /// In the Windows SDK set up by a Visual Studio Developer Command Prompt, in `$WindowsSdkVerBinPath` or `$WindowsSdkDir`
+    DeveloperPrompt,
+    /// In the Windows 8 to 10 kits in the registry, under `KitsRoot10`, `KitsRoot81`, or `KitsRoot`
+    WindowsKits,
+    /// In the Windows Vista to 7 SDK in the registry
+    WindowsSdk,
+    /// In one of the versioned Windows 10 kits
+    Windows10Kits,
+    /// In the Windows SDK or Visual Studio found by vswhom
+    Vswhom,
+    /// Next to the [linker](struct.Options.html#method.linker)
+    Linker,
+    /// Nowhere, so it's run by name from `$PATH`
+    Path,
+}
+
+impl DiscoveryStrategy {
+    /// The name passed to the [discovery callback](fn.set_discovery_callback.html), like `"windows-10-kits"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DiscoveryStrategy::DeveloperPrompt => "developer-prompt",
+            DiscoveryStrategy::WindowsKits => "windows-kits",
+            DiscoveryStrategy::WindowsSdk => "windows-sdk",
+            DiscoveryStrategy::Windows10Kits => "windows-10-kits",
+            DiscoveryStrategy::Vswhom => "vswhom",
+            DiscoveryStrategy::Linker => "linker",
+            DiscoveryStrategy::Path => "path",
+        }
+    }
+}
+
+impl fmt::Display for DiscoveryStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+
+type DiscoveryCallback = Box<dyn Fn(&str, &Path, bool) + Send + Sync>;
+
+static CALLBACK: AtomicPtr<RwLock<Option<DiscoveryCallback>>> = AtomicPtr::new(ptr::null_mut());
+/// Checked before taking the lock, so that probing costs nothing extra without a callback
+static HAS_CALLBACK: AtomicBool = AtomicBool::new(false);
+
+fn registered_callback() -> &'static RwLock<Option<DiscoveryCallback>> {
+    lazy(&CALLBACK, || RwLock::new(None))
+}
+
+
+/// Register a callback to be called with each candidate path probed while looking for the resource compiler
+/// and other tools, with the name of the discovery strategy, the path, and whether it exists,
+/// for example to report discovery in a tool's own UI; this replaces the previously registered callback, if any.
+///
+/// The strategies are [`DiscoveryStrategy`](enum.DiscoveryStrategy.html) [names](enum.DiscoveryStrategy.html#method.name),
+/// tried with MSVC in the order they're declared in, except `"linker"`, which is the only one for `windres`,
+/// and `"path"`, since tools run from `$PATH` aren't probed.
+///
+/// The callback is called on the thread doing the discovery, and mustn't register or clear callbacks itself.
+/// With MSVC, discovery is done once per tool and target architecture per process, so register the callback before the first compilation.
+///
+/// # Examples
+///
+/// ```rust
+/// embed_resource::set_discovery_callback(|strategy, candidate, exists| {
+///     eprintln!("{}: {} {}", strategy, candidate.display(), if exists { "found" } else { "not found" });
+/// });
+/// # embed_resource::clear_discovery_callback();
+/// ```
+pub fn set_discovery_callback<F: Fn(&str, &Path, bool) + Send + Sync + 'static>(callback: F) {
+    *registered_callback().write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(callback));
+    HAS_CALLBACK.store(true, SeqCst);
+}
+
+/// Unregister the callback registered with [`set_discovery_callback()`](fn.set_discovery_callback.html), if any.
+pub fn clear_discovery_callback() {
+    HAS_CALLBACK.store(false, SeqCst);
+    *registered_callback().write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Report the probed candidate to the callback, if any
+pub(crate) fn probed(strategy: DiscoveryStrategy, candidate: &Path, exists: bool) {
+    if HAS_CALLBACK.load(SeqCst) {
+        if let Some(ref callback) = *registered_callback().read().unwrap_or_else(|e| e.into_inner()) {
+            callback(strategy.name(), candidate, exists);
+        }
+    }
+}
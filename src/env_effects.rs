@@ -0,0 +1,86 @@
+use super::options::ENV_DEFINE_PREFIX;
+use std::env;
+
+
+/// How the crate interacts with an environment variable, as reported by [`environment_effects()`](fn.environment_effects.html).
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EnvEffect {
+    /// The variable is only read
+    Read,
+    /// The variable is read and modified in this process' environment
+    Modified {
+        /// Whether the original value is restored after compiling;
+        /// if not, the modification is visible to the rest of the build script and everything it spawns
+        restored: bool,
+    },
+}
+
+
+/// The environment variables read or modified by this crate on this host, with how.
+///
+/// Variables that are merely inherited by the resource compiler aren't listed,
+/// and the `EMBED_RESOURCE_DEFINE_` family is listed as `EMBED_RESOURCE_DEFINE_*`.
+///
+/// Note that [`compile_explicit()`](fn.compile_explicit.html) itself only reads `PATH` (for
+/// [`Options::record_compile_commands()`](struct.Options.html#method.record_compile_commands)) and `EMBED_RESOURCE_DEBUG`,
+/// but, with MSVC, still reads `WindowsSdkVerBinPath`, `WindowsSdkDir`, and `WindowsSDKVersion`, and modifies `INCLUDE` unless [`Options::explicit_includes()`](struct.Options.html#method.explicit_includes) is set.
+///
+/// # Examples
+///
+/// ```rust
+/// # use embed_resource::{EnvEffect, environment_effects};
+/// for (var, effect) in environment_effects() {
+///     if effect != EnvEffect::Read {
+///         println!("embed-resource modifies ${}", var);
+///     }
+/// }
+/// ```
+pub fn environment_effects() -> Vec<(&'static str, EnvEffect)> {
+    let mut effects = vec![("TARGET", EnvEffect::Read),
+                           ("OUT_DIR", EnvEffect::Read),
+                           ("EMBED_RESOURCE_DEFINE_*", EnvEffect::Read),
+                           ("EMBED_RESOURCE_DEBUG", EnvEffect::Read),
+                           ("EMBED_RESOURCE_TIMING", EnvEffect::Read),
+                           ("EMBED_RESOURCE_FORCE", EnvEffect::Read),
+                           // Only by VersionInfo::git_commit()
+                           ("EMBED_RESOURCE_GIT_HASH", EnvEffect::Read),
+                           ("SOURCE_DATE_EPOCH", EnvEffect::Read),
+                           ("PATH", EnvEffect::Read),
+                           // For whether LINK.EXE will generate a manifest that conflicts with compile_manifest()'s
+                           ("CARGO_ENCODED_RUSTFLAGS", EnvEffect::Read)];
+    if !cfg!(all(target_os = "windows", target_env = "msvc")) {
+        // To find the matching windres
+        effects.push(("RUSTC_LINKER", EnvEffect::Read));
+    }
+    if !cfg!(target_os = "windows") {
+        // Enables compiling for MSVC targets with llvm-rc
+        effects.push(("EMBED_RESOURCE_WINDOWS_SDK", EnvEffect::Read));
+    }
+    if cfg!(all(target_os = "windows", target_env = "msvc")) {
+        // Set by Visual Studio Developer Command Prompts, and checked for RC.EXE first
+        effects.push(("WindowsSdkVerBinPath", EnvEffect::Read));
+        effects.push(("WindowsSdkDir", EnvEffect::Read));
+        // Also set by some CI images, selecting the Windows 10 SDK version
+        effects.push(("WindowsSDKVersion", EnvEffect::Read));
+        // Checked before looking for CVTRES.EXE with vswhom, for Options::object()
+        effects.push(("VCINSTALLDIR", EnvEffect::Read));
+        // Windows 10 SDK include directories are appended by include_windows_10_kits(), unless it has windows.h already
+        effects.push(("INCLUDE", EnvEffect::Modified { restored: false }));
+    }
+    effects
+}
+
+/// The variables a change to which should rerun the build script, printed as `cargo:rerun-if-env-changed`:
+/// those in [`environment_effects()`](fn.environment_effects.html), except cargo's own `TARGET`, `OUT_DIR`, and `CARGO_ENCODED_RUSTFLAGS`,
+/// `PATH`, which differs between shells and IDEs, and would rerun it spuriously, and `EMBED_RESOURCE_DEFINE_*`,
+/// which is expanded to the ones currently set
+pub(crate) fn tracked_env_vars() -> Vec<String> {
+    let mut ret: Vec<String> = environment_effects()
+        .into_iter()
+        .map(|(var, _)| var)
+        .filter(|&var| !["TARGET", "OUT_DIR", "CARGO_ENCODED_RUSTFLAGS", "PATH", "EMBED_RESOURCE_DEFINE_*"].contains(&var))
+        .map(str::to_string)
+        .collect();
+    ret.extend(env::vars_os().filter_map(|(var, _)| var.into_string().ok()).filter(|var| var.starts_with(ENV_DEFINE_PREFIX)));
+    ret
+}
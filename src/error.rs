@@ -0,0 +1,158 @@
+use super::{ManifestError, Diagnostic, ResourceId};
+use std::process::ExitStatus;
+use std::error::Error;
+use std::path::PathBuf;
+use std::{fmt, io};
+
+
+/// Why compiling a resource failed.
+///
+/// # Examples
+///
+/// ```rust
+/// # use embed_resource::{CompileError, Options, compile_explicit};
+/// # use std::path::Path;
+/// match compile_explicit(Path::new("checksums.rc"), Path::new("/nonexistent/out"), "x86_64-pc-windows-gnu", Options::new()) {
+///     Err(CompileError::OutputDirNotWritable(dir, _)) => assert_eq!(dir, Path::new("/nonexistent/out")),
+///     other => panic!("{:?}", other),
+/// }
+/// ```
+#[derive(Debug)]
+pub enum CompileError {
+    /// The target isn't Windows, or there's no resource compiler for it
+    UnsupportedTarget(String),
+    /// `$OUT_DIR` isn't set, since this isn't a build script
+    MissingOutDir,
+    /// The path has no file stem or isn't UTF-8
+    InvalidPath(PathBuf),
+    /// Couldn't write a generated file or read the compiled resource
+    Io(PathBuf, io::Error),
+    /// The output directory doesn't exist or can't be written to
+    OutputDirNotWritable(PathBuf, io::Error),
+    /// Couldn't run the resource compiler
+    Spawn(PathBuf, io::Error),
+    /// The resource compiler wasn't found anywhere, but the MSVC compiler was, at the specified path,
+    /// so the Windows SDK component isn't installed
+    MissingWindowsSdk(PathBuf),
+    /// The resource compiler exited unsuccessfully
+    CompilerFailed(PathBuf, ExitStatus),
+    /// The resource compiler and all the alternatives tried with
+    /// [`Options::try_alternative_compilers()`](struct.Options.html#method.try_alternative_compilers) failed, in order
+    AllCompilersFailed(Vec<CompileError>),
+    /// The resource compiler reported warnings and [`Options::warnings_as_errors()`](struct.Options.html#method.warnings_as_errors) was set
+    Warnings(Vec<Diagnostic>),
+    /// The compiled resource is missing types specified with
+    /// [`Options::expect_resource_type()`](struct.Options.html#method.expect_resource_type)
+    MissingResourceTypes(Vec<ResourceId>),
+    /// The resource scripts would be compiled to the same file, for [`compile_dir()`](fn.compile_dir.html)
+    DuplicatePrefix(PathBuf, PathBuf),
+    /// The accelerator key is invalid or can't be combined with the modifiers, for [`compile_accelerators()`](fn.compile_accelerators.html)
+    InvalidAccelerator(String),
+    /// A size, coordinate, style, or class of a [`DialogSpec`](struct.DialogSpec.html) is invalid
+    InvalidDialog(String),
+    /// The resource name or type isn't an identifier, for [`compile_resource_entries()`](fn.compile_resource_entries.html)
+    InvalidResourceName(String),
+    /// The file for [`compile_xml()`](fn.compile_xml.html) isn't well-formed XML, for the specified reason
+    MalformedXml(PathBuf, String),
+    /// The warning code passed to [`Options::suppress_warning()`](struct.Options.html#method.suppress_warning) isn't letters followed by digits
+    InvalidWarningCode(String),
+    /// The file for [`compile_bitmap()`](fn.compile_bitmap.html) isn't a BMP, for the specified reason
+    InvalidBitmap(PathBuf, String),
+    /// The file for [`compile_cursor()`](fn.compile_cursor.html) isn't a static (`.cur`) or animated (`.ani`) cursor, for the specified reason
+    InvalidCursor(PathBuf, String),
+    /// The string ID was specified twice, or translated without a neutral string,
+    /// for [`compile_localized_strings()`](fn.compile_localized_strings.html)
+    InvalidStringId(u16),
+    /// The `LANGID` is a neutral primary language with a non-neutral sublanguage, like `LANG_USER_DEFAULT`,
+    /// for [`Options::language()`](struct.Options.html#method.language) or [`VersionInfo::language()`](struct.VersionInfo.html#method.language)
+    InvalidLanguage(u16),
+    /// The `LANGID` was specified twice, or is neutral, for [`compile_localized()`](fn.compile_localized.html) or [`compile_localized_strings()`](fn.compile_localized_strings.html)
+    DuplicateLanguage(u16),
+    /// The Windows SDK doesn't have headers for the version passed to
+    /// [`Options::sdk_include_version()`](struct.Options.html#method.sdk_include_version)
+    MissingSdkIncludeVersion(String),
+    /// The `cfg` predicate is malformed or uses unsupported syntax, for [`compile_cfg()`](fn.compile_cfg.html)
+    InvalidCfgPredicate(String),
+    /// The path uses a `$(variable)` that wasn't set with [`Options::path_variable()`](struct.Options.html#method.path_variable)
+    UnknownPathVariable(String, PathBuf),
+    /// The generated manifest is invalid
+    Manifest(ManifestError),
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CompileError::UnsupportedTarget(ref target) => write!(f, "no resource compiler for target \"{}\"", target),
+            CompileError::MissingOutDir => {
+                f.write_str("OUT_DIR is not set: this isn't a build script; use compile_explicit() to compile into a directory of your choosing")
+            }
+            CompileError::InvalidPath(ref path) => write!(f, "\"{}\" has no file stem or is not UTF-8", path.display()),
+            CompileError::Io(ref path, ref err) => write!(f, "\"{}\": {}", path.display(), err),
+            CompileError::OutputDirNotWritable(ref dir, ref err) => write!(f, "output directory \"{}\" is not writable: {}", dir.display(), err),
+            CompileError::Spawn(ref compiler, ref err) => write!(f, "couldn't execute {}: {}", compiler.display(), err),
+            CompileError::MissingWindowsSdk(ref cl) => {
+                write!(f,
+                       "found the MSVC compiler at {}, but no RC.EXE: install a Windows 10 or 11 SDK, \
+                        under \"Individual components\" in the Visual Studio Installer",
+                       cl.display())
+            }
+            CompileError::CompilerFailed(ref compiler, ref status) => write!(f, "{} failed with {}", compiler.display(), status),
+            CompileError::AllCompilersFailed(ref errors) => {
+                f.write_str("all resource compilers failed:")?;
+                for err in errors {
+                    write!(f, "\n{}", err)?;
+                }
+                Ok(())
+            }
+            CompileError::Warnings(ref warnings) => {
+                f.write_str("warnings treated as errors:")?;
+                for warning in warnings {
+                    write!(f, "\n{}", warning)?;
+                }
+                Ok(())
+            }
+            CompileError::MissingResourceTypes(ref missing) => {
+                f.write_str("missing resource type(s)")?;
+                for (i, ty) in missing.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 { " " } else { ", " }, ty)?;
+                }
+                Ok(())
+            }
+            CompileError::DuplicatePrefix(ref first, ref second) => {
+                write!(f, "\"{}\" and \"{}\" would be compiled to the same file", first.display(), second.display())
+            }
+            CompileError::InvalidAccelerator(ref accelerator) => write!(f, "invalid accelerator {}", accelerator),
+            CompileError::InvalidDialog(ref what) => write!(f, "invalid dialog {}", what),
+            CompileError::InvalidResourceName(ref name) => write!(f, "resource name or type \"{}\" is not an identifier", name),
+            CompileError::MalformedXml(ref path, ref reason) => write!(f, "{} is not well-formed XML: {}", path.display(), reason),
+            CompileError::InvalidWarningCode(ref code) => write!(f, "invalid warning code \"{}\"", code),
+            CompileError::InvalidBitmap(ref path, ref reason) => write!(f, "{} is not a BMP: {}", path.display(), reason),
+            CompileError::InvalidCursor(ref path, ref reason) => write!(f, "{} is not a cursor: {}", path.display(), reason),
+            CompileError::InvalidStringId(id) => write!(f, "string ID {} is duplicated or has no neutral string", id),
+            CompileError::InvalidLanguage(langid) => write!(f, "LANGID 0x{:04x} is not a specific language", langid),
+            CompileError::DuplicateLanguage(langid) => write!(f, "duplicate language section for LANGID 0x{:04x}", langid),
+            CompileError::MissingSdkIncludeVersion(ref version) => write!(f, "no Windows SDK include directory for version {}", version),
+            CompileError::InvalidCfgPredicate(ref predicate) => write!(f, "malformed or unsupported cfg predicate \"{}\"", predicate),
+            CompileError::UnknownPathVariable(ref name, ref path) => write!(f, "unknown variable $({}) in \"{}\"", name, path.display()),
+            CompileError::Manifest(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for CompileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            CompileError::Io(_, ref err) |
+            CompileError::OutputDirNotWritable(_, ref err) |
+            CompileError::Spawn(_, ref err) => Some(err),
+            CompileError::Manifest(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ManifestError> for CompileError {
+    fn from(err: ManifestError) -> CompileError {
+        CompileError::Manifest(err)
+    }
+}
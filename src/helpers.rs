@@ -0,0 +1,1082 @@
+use super::{CompileError, CombinedScript, ScriptSection, CrateType, AcceleratorKey, Modifiers, DialogSpec, ScriptEncoding, ResourceId, VersionInfo, ResourceCompiler, Manifest, ExecutionLevel, Artifact, Options, compile_impl, default_prefix, is_valid_langid, out_dir};
+use super::accelerators::accelerators_script;
+use super::version_info::rc_quote;
+use super::manifest::check_well_formed;
+use super::cfg;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+
+/// The type of a resource read from a file with [`compile_resource_entries()`](fn.compile_resource_entries.html).
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ResourceType<'a> {
+    /// `RT_HTML`, for `res://` URLs
+    Html,
+    /// `RT_RCDATA`, arbitrary binary data
+    RcData,
+    /// `RT_BITMAP`, from a `.bmp`
+    Bitmap,
+    /// `RT_GROUP_CURSOR`, from a `.cur`
+    Cursor,
+    /// `RT_GROUP_ICON`, from a `.ico`
+    Icon,
+    /// A custom type with the specified name
+    Custom(&'a str),
+}
+
+
+/// Compile and link all resource scripts (`*.rc`) in the specified directory, and, if `recursive`, its subdirectories,
+/// returning the compiled resources, if the target is Windows.
+///
+/// The scripts are compiled in order of their paths, and `cargo:rerun-if-changed` is emitted for the directory and each script,
+/// see [generated resources](index.html#generated-resources).
+/// Scripts with the same [prefix](fn.default_prefix.html), like `a/app.rc` and `b/app.rc`, would overwrite each other, so are an error.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+///
+/// fn main() {
+///     embed_resource::compile_dir("resources", false).unwrap();
+/// }
+/// ```
+pub fn compile_dir<P: AsRef<Path>>(dir: P, recursive: bool) -> Result<Vec<Artifact>, CompileError> {
+    let dir = dir.as_ref();
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut scripts = vec![];
+    find_scripts(dir, recursive, &mut scripts)?;
+    scripts.sort();
+
+    check_distinct_prefixes(&scripts.iter().map(PathBuf::as_path).collect::<Vec<_>>())?;
+
+    let mut ret = vec![];
+    for script in &scripts {
+        println!("cargo:rerun-if-changed={}", script.display());
+        ret.extend(compile_impl(script, Options::new())?);
+    }
+    Ok(ret)
+}
+
+/// Scripts with the same [prefix](fn.default_prefix.html) would overwrite each other's output, so are an error
+fn check_distinct_prefixes(scripts: &[&Path]) -> Result<(), CompileError> {
+    let mut prefixes: Vec<(String, &Path)> = vec![];
+    for &script in scripts {
+        let prefix = default_prefix(script).ok_or_else(|| CompileError::InvalidPath(script.to_path_buf()))?;
+        if let Some(&(_, other)) = prefixes.iter().find(|p| p.0 == prefix) {
+            return Err(CompileError::DuplicatePrefix(other.to_path_buf(), script.to_path_buf()));
+        }
+        prefixes.push((prefix, script));
+    }
+    Ok(())
+}
+
+/// Compile and link the resource scripts whose `cfg` predicate holds for the target being built for,
+/// returning the compiled resources, if the target is Windows.
+///
+/// This replaces `if` chains over `$CARGO_CFG_*` in build scripts (`cfg!()` checks the host, not the target).
+/// The predicates are a subset of Rust's `cfg` syntax:
+///   * `name = "value"`, like `target_arch = "x86_64"`, `target_os = "windows"`, or `target_env = "gnu"`,
+///     which holds if `$CARGO_CFG_{NAME}` is, or for comma-separated lists like `target_feature`, contains, the value,
+///     or, for `feature = "name"`, if the feature is enabled,
+///   * `name`, like `debug_assertions`, which holds if `$CARGO_CFG_{NAME}` is set,
+///   * `all(...)`, `any(...)`, and `not(...)` of these.
+///
+/// The selected scripts are compiled in order, like by [`compile_dir()`](fn.compile_dir.html), so must have different [prefixes](fn.default_prefix.html),
+/// and `cargo:rerun-if-changed` is emitted for each of them, but not for the others.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+///
+/// fn main() {
+///     embed_resource::compile_cfg(&[("any(target_arch = \"x86\", target_arch = \"x86_64\")", "resources/x86.rc"),
+///                                   ("target_arch = \"aarch64\"", "resources/arm64.rc"),
+///                                   ("feature = \"tray\"", "resources/tray.rc")])
+///         .unwrap();
+/// }
+/// ```
+pub fn compile_cfg<P: AsRef<Path>>(resources: &[(&str, P)]) -> Result<Vec<Artifact>, CompileError> {
+    let mut scripts: Vec<&Path> = vec![];
+    for &(predicate, ref script) in resources {
+        if cfg::matches(predicate).ok_or_else(|| CompileError::InvalidCfgPredicate(predicate.to_string()))? {
+            scripts.push(script.as_ref());
+        }
+    }
+
+    check_distinct_prefixes(&scripts)?;
+
+    let mut ret = vec![];
+    for script in scripts {
+        println!("cargo:rerun-if-changed={}", script.display());
+        ret.extend(compile_impl(script, Options::new())?);
+    }
+    Ok(ret)
+}
+
+/// Generate, compile, and link a manifest resource.
+///
+/// The manifest is written to `$OUT_DIR/embed_resource_manifest.manifest` and embedded with the ID
+/// `CREATEPROCESS_MANIFEST_RESOURCE_ID` (1), which is where the loader looks for an executable's manifest;
+/// use [`compile_manifest_with()`](fn.compile_manifest_with.html) for DLLs.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::{Manifest, SupportedOs};
+///
+/// fn main() {
+///     embed_resource::compile_manifest(&Manifest::new()
+///         .supported_os(SupportedOs::Windows7)
+///         .supported_os(SupportedOs::Windows81)
+///         .supported_os(SupportedOs::Windows10))
+///         .unwrap();
+/// }
+/// ```
+pub fn compile_manifest(manifest: &Manifest) -> Result<Option<Artifact>, CompileError> {
+    compile_manifest_with(manifest, Options::new())
+}
+
+/// Like [`compile_manifest()`](fn.compile_manifest.html), but with the specified [`Options`](struct.Options.html),
+/// embedding the manifest with the ID appropriate for [`Options::crate_type()`](struct.Options.html#method.crate_type).
+///
+/// For MSVC targets, this also handles the conflict with `LINK.EXE`'s own manifest,
+/// see [`Options::disable_linker_manifest()`](struct.Options.html#method.disable_linker_manifest).
+///
+/// # Examples
+///
+/// In the build script of a `cdylib`:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::{CrateType, Manifest, Options, SupportedOs};
+///
+/// fn main() {
+///     embed_resource::compile_manifest_with(&Manifest::new().supported_os(SupportedOs::Windows10),
+///                                           Options::new().crate_type(CrateType::Cdylib))
+///         .unwrap();
+/// }
+/// ```
+pub fn compile_manifest_with(manifest: &Manifest, options: Options) -> Result<Option<Artifact>, CompileError> {
+    let xml = manifest.to_xml()?;
+
+    let manifest_path = generated_dir(&options)?.join("embed_resource_manifest.manifest");
+    fs::write(&manifest_path, xml).map_err(|e| CompileError::Io(manifest_path.clone(), e))?;
+
+    // 24 is RT_MANIFEST
+    let id = options.crate_type.unwrap_or_default().manifest_resource_id();
+    let (crate_type, disable_linker_manifest, suppress_directives) =
+        (options.crate_type.unwrap_or_default(), options.disable_linker_manifest, options.suppress_directives.unwrap_or(false));
+    let artifact = compile_generated("embed_resource_manifest", &format!("{} 24 {}\n", id, rc_string_literal(&manifest_path)?), options)?;
+    if artifact.is_some() && !suppress_directives {
+        handle_linker_manifest(crate_type, disable_linker_manifest);
+    }
+    Ok(artifact)
+}
+
+/// LINK.EXE's own manifest, if embedded, is a second RT_MANIFEST resource with the same ID as ours;
+/// windres targets link with ld or lld in MinGW mode, which don't generate manifests
+fn handle_linker_manifest(crate_type: CrateType, disable_linker_manifest: Option<bool>) {
+    if !env::var("TARGET").map(|t| t.ends_with("-msvc")).unwrap_or(false) {
+        return;
+    }
+    match disable_linker_manifest {
+        Some(true) => {
+            match crate_type {
+                CrateType::Bin => println!("cargo:rustc-link-arg=/MANIFEST:NO"),
+                CrateType::Cdylib => println!("cargo:rustc-cdylib-link-arg=/MANIFEST:NO"),
+            }
+        }
+        Some(false) => {}
+        None if env::var("CARGO_ENCODED_RUSTFLAGS").map(|flags| rustflags_request_manifest(&flags)).unwrap_or(false) => {
+            println!("cargo:warning=embedding a manifest resource may conflict with the one generated by LINK.EXE; \
+                      use Options::disable_linker_manifest() to pass /MANIFEST:NO or silence this warning")
+        }
+        None => {}
+    }
+}
+
+/// Whether the `\x1F`-separated rustflags pass `LINK.EXE` `/MANIFEST` or `/MANIFEST:EMBED`, as `-C link-arg`s or `-C link-args`;
+/// without either, rustc doesn't have it generate a manifest
+fn rustflags_request_manifest(flags: &str) -> bool {
+    flags.split(|c: char| c == '\x1F' || c == '=' || c.is_whitespace()).any(|arg| {
+        let arg = arg.to_ascii_uppercase();
+        (arg.starts_with('/') || arg.starts_with('-')) && (&arg[1..] == "MANIFEST" || arg[1..].starts_with("MANIFEST:EMBED"))
+    })
+}
+
+/// Merge the specified manifest files into one, and compile and link it, like [`compile_manifest()`](fn.compile_manifest.html).
+///
+/// Each file is included as a [`Manifest::fragment()`](struct.Manifest.html#method.fragment), in order,
+/// so they can be whole manifests or just the elements to go into `<assembly>`.
+/// This emits `cargo:rerun-if-changed` for each file.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+///
+/// fn main() {
+///     embed_resource::compile_manifest_fragments(&["manifest/dpi.manifest", "manifest/common-controls.manifest"]).unwrap();
+/// }
+/// ```
+pub fn compile_manifest_fragments<P: AsRef<Path>>(fragments: &[P]) -> Result<Option<Artifact>, CompileError> {
+    let mut manifest = Manifest::new();
+    for path in fragments {
+        let path = path.as_ref();
+        println!("cargo:rerun-if-changed={}", path.display());
+        manifest = manifest.fragment(fs::read_to_string(path).map_err(|e| CompileError::Io(path.to_path_buf(), e))?);
+    }
+    compile_manifest(&manifest)
+}
+
+/// Generate, compile, and link a manifest requesting the specified execution level, like [`compile_manifest()`](fn.compile_manifest.html).
+///
+/// This is shorthand for a [`Manifest`](struct.Manifest.html) with just an [execution level](struct.Manifest.html#method.execution_level);
+/// build one to also declare other settings or merge it with your own manifest.
+///
+/// # Examples
+///
+/// In the build script of an installer:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::ExecutionLevel;
+///
+/// fn main() {
+///     embed_resource::compile_uac_manifest(ExecutionLevel::RequireAdministrator).unwrap();
+/// }
+/// ```
+pub fn compile_uac_manifest(level: ExecutionLevel) -> Result<Option<Artifact>, CompileError> {
+    compile_manifest(&Manifest::new().execution_level(level))
+}
+
+/// Generate, compile, and link a manifest making UTF-8 the process' active code page, like [`compile_manifest()`](fn.compile_manifest.html).
+///
+/// This is shorthand for a [`Manifest`](struct.Manifest.html) with just the [UTF-8 code page](struct.Manifest.html#method.utf8_code_page),
+/// so that the C runtime and the `*A()` functions take and return UTF-8 on Windows 10 1903 and newer, without `chcp 65001`.
+///
+/// # Examples
+///
+/// In the build script of a console application:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+///
+/// fn main() {
+///     embed_resource::compile_utf8_manifest().unwrap();
+/// }
+/// ```
+pub fn compile_utf8_manifest() -> Result<Option<Artifact>, CompileError> {
+    compile_manifest(&Manifest::new().utf8_code_page(true))
+}
+
+/// Generate, compile, and link a manifest, version information, and resources read from files as one resource script,
+/// with the sections in the [order](struct.CombinedScript.html#method.order) specified.
+///
+/// The resource script is written to `$OUT_DIR/embed_resource_combined.rc`, and the manifest, if any, to `$OUT_DIR/embed_resource_combined.manifest`,
+/// or to the [temporary directory](struct.Options.html#method.temp_dir), if specified.
+/// The manifest is handled like by [`compile_manifest_with()`](fn.compile_manifest_with.html),
+/// and `cargo:rerun-if-changed` is emitted for each entry, like by [`compile_resource_entries()`](fn.compile_resource_entries.html).
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::{CombinedScript, Manifest, Options, ResourceId, ResourceType, ScriptSection, SupportedOs, VersionInfo};
+///
+/// fn main() {
+///     let script = CombinedScript::new()
+///         .manifest(Manifest::new().supported_os(SupportedOs::Windows10))
+///         .version_info(VersionInfo::from_cargo_env())
+///         .entry(ResourceType::Icon, ResourceId::Ordinal(1), "res/app.ico")
+///         .order(&[ScriptSection::Icons]);
+///     embed_resource::compile_combined(&script, Options::new().disable_linker_manifest(true)).unwrap();
+/// }
+/// ```
+pub fn compile_combined(script: &CombinedScript, options: Options) -> Result<Option<Artifact>, CompileError> {
+    // For the paths, before the version information would set it
+    let mut rc = "#pragma code_page(65001)\n".to_string();
+    for &section in script.sections() {
+        match section {
+            ScriptSection::Manifest => {
+                if let Some(ref manifest) = script.manifest {
+                    let manifest_path = generated_dir(&options)?.join("embed_resource_combined.manifest");
+                    fs::write(&manifest_path, manifest.to_xml()?).map_err(|e| CompileError::Io(manifest_path.clone(), e))?;
+                    // 24 is RT_MANIFEST
+                    rc.push_str(&format!("{} 24 {}\n", options.crate_type.unwrap_or_default().manifest_resource_id(), rc_string_literal(&manifest_path)?));
+                }
+            }
+            ScriptSection::VersionInfo => {
+                if let Some(ref version_info) = script.version_info {
+                    if let Some(langid) = version_info.language.filter(|&l| !is_valid_langid(l)) {
+                        return Err(CompileError::InvalidLanguage(langid));
+                    }
+                    rc.push_str(&version_info.to_rc());
+                }
+            }
+            ScriptSection::Icons | ScriptSection::Other => {
+                for &(resource_type, ref name, ref path) in &script.entries {
+                    if (resource_type == ResourceType::Icon) == (section == ScriptSection::Icons) {
+                        rc.push_str(&entry_line(resource_type, name, &options.expand_path(path)?)?);
+                    }
+                }
+            }
+        }
+    }
+
+    let (crate_type, disable_linker_manifest, suppress_directives) =
+        (options.crate_type.unwrap_or_default(), options.disable_linker_manifest, options.suppress_directives.unwrap_or(false));
+    let artifact = compile_generated("embed_resource_combined", &rc, options)?;
+    if artifact.is_some() && script.manifest.is_some() && !suppress_directives {
+        handle_linker_manifest(crate_type, disable_linker_manifest);
+    }
+    Ok(artifact)
+}
+
+/// Generate, compile, and link a version information resource.
+///
+/// The resource script is written to `$OUT_DIR/embed_resource_version_info.rc`, or to the [temporary directory](struct.Options.html#method.temp_dir) with [`compile_version_info_with()`](fn.compile_version_info_with.html).
+///
+/// # Examples
+///
+/// In your build script, to show the crate's version and the commit being built in Explorer:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::VersionInfo;
+///
+/// fn main() {
+///     embed_resource::compile_version_info(&VersionInfo::from_cargo_env()
+///         .string("CompanyName", "Checksum Enthusiasts")
+///         .git_commit("GitCommit", true))
+///         .unwrap();
+/// }
+/// ```
+pub fn compile_version_info(version_info: &VersionInfo) -> Result<Option<Artifact>, CompileError> {
+    compile_version_info_with(version_info, Options::new())
+}
+
+/// Like [`compile_version_info()`](fn.compile_version_info.html), but with the specified [options](index.html#generated-resources).
+pub fn compile_version_info_with(version_info: &VersionInfo, options: Options) -> Result<Option<Artifact>, CompileError> {
+    if let Some(langid) = version_info.language.filter(|&l| !is_valid_langid(l)) {
+        return Err(CompileError::InvalidLanguage(langid));
+    }
+    compile_generated("embed_resource_version_info", &version_info.to_rc(), options)
+}
+
+/// Compile and link the specified resource scripts as one, with the first declaring language-neutral resources
+/// and the rest localized ones for the specified `LANGID`s, like `0x0409` for U.S. English.
+///
+/// A resource script consisting of a `#pragma code_page(65001)`, then a `LANGUAGE LANG_NEUTRAL, SUBLANG_NEUTRAL` section
+/// `#include`ing the neutral script, then a `LANGUAGE` section for each localized script, is written to
+/// `$OUT_DIR/embed_resource_localized.rc`, or the [temporary directory](struct.Options.html#method.temp_dir) with [`compile_localized_with()`](fn.compile_localized_with.html), and compiled.
+/// This way `FindResourceEx()` & co. fall back to the neutral resources for languages without localized ones.
+///
+/// The included scripts mustn't contain `LANGUAGE` statements of their own.
+/// A localized `LANGID` of `0` (neutral) or one specified twice is an error, so exactly one neutral section is emitted.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+///
+/// fn main() {
+///     embed_resource::compile_localized("res/neutral.rc", &[(0x0409, "res/en-us.rc"), (0x0407, "res/de-de.rc")]).unwrap();
+/// }
+/// ```
+pub fn compile_localized<N: AsRef<Path>, L: AsRef<Path>>(neutral: N, localized: &[(u16, L)]) -> Result<Option<Artifact>, CompileError> {
+    compile_localized_with(neutral, localized, Options::new())
+}
+
+/// Like [`compile_localized()`](fn.compile_localized.html), but with the specified [options](index.html#generated-resources).
+pub fn compile_localized_with<N: AsRef<Path>, L: AsRef<Path>>(neutral: N, localized: &[(u16, L)], options: Options) -> Result<Option<Artifact>, CompileError> {
+    let mut script = "#pragma code_page(65001)\n".to_string();
+    // LANG_NEUTRAL, SUBLANG_NEUTRAL
+    script.push_str(&format!("LANGUAGE 0x0, 0x0\n#include {}\n", rc_string_literal(&absolute_path(&options.expand_path(neutral.as_ref())?))?));
+
+    let mut seen = vec![0];
+    for &(langid, ref path) in localized {
+        if seen.contains(&langid) {
+            return Err(CompileError::DuplicateLanguage(langid));
+        }
+        seen.push(langid);
+
+        // LANGID is MAKELANGID(primary, sub), i.e. sub << 10 | primary
+        script.push_str(&format!("LANGUAGE 0x{:x}, 0x{:x}\n#include {}\n",
+                                 langid & 0x3FF,
+                                 langid >> 10,
+                                 rc_string_literal(&absolute_path(&options.expand_path(path.as_ref())?))?));
+    }
+
+    compile_generated("embed_resource_localized", &script, options)
+}
+
+/// Generate, compile, and link a string table for `LoadString()`, with the specified neutral strings,
+/// and translations of them for the specified `LANGID`s, like `0x0407` for German.
+///
+/// The neutral strings are declared under `LANGUAGE LANG_NEUTRAL, SUBLANG_NEUTRAL`, and each language gets a `STRINGTABLE` of its own
+/// with all of them, translated where a translation is specified.
+/// This is because strings are stored in blocks of 16 consecutive IDs, and `LoadString()` doesn't fall back to other languages
+/// for strings missing from a block it found.
+///
+/// As with [`compile_localized()`](fn.compile_localized.html), a `LANGID` of `0` or one specified twice is an error,
+/// and so are neutral string IDs specified twice and translations of IDs without a neutral string.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+///
+/// fn main() {
+///     embed_resource::compile_localized_strings(&[(101, "Checksums"), (102, "Verify all")],
+///                                               &[(0x0407, &[(102, "Alle prüfen")]),
+///                                                 (0x040C, &[(102, "Tout vérifier")])])
+///         .unwrap();
+/// }
+/// ```
+pub fn compile_localized_strings(neutral: &[(u16, &str)], localized: &[(u16, &[(u16, &str)])]) -> Result<Option<Artifact>, CompileError> {
+    compile_localized_strings_with(neutral, localized, Options::new())
+}
+
+/// Like [`compile_localized_strings()`](fn.compile_localized_strings.html), but with the specified [options](index.html#generated-resources).
+pub fn compile_localized_strings_with(neutral: &[(u16, &str)], localized: &[(u16, &[(u16, &str)])], options: Options) -> Result<Option<Artifact>, CompileError> {
+    for (i, &(id, _)) in neutral.iter().enumerate() {
+        if neutral[..i].iter().any(|&(other, _)| other == id) {
+            return Err(CompileError::InvalidStringId(id));
+        }
+    }
+
+    let mut script = "#pragma code_page(65001)\n".to_string();
+    script.push_str(&string_table(0, neutral, &[]));
+    let mut seen = vec![0];
+    for &(langid, translations) in localized {
+        if seen.contains(&langid) {
+            return Err(CompileError::DuplicateLanguage(langid));
+        }
+        seen.push(langid);
+        if let Some(&(id, _)) = translations.iter().find(|&&(id, _)| !neutral.iter().any(|&(n, _)| n == id)) {
+            return Err(CompileError::InvalidStringId(id));
+        }
+
+        script.push_str(&string_table(langid, neutral, translations));
+    }
+
+    compile_generated("embed_resource_localized_strings", &script, options)
+}
+
+/// A `LANGUAGE` statement and `STRINGTABLE` with the neutral strings, translated where possible
+fn string_table(langid: u16, neutral: &[(u16, &str)], translations: &[(u16, &str)]) -> String {
+    // LANGID is MAKELANGID(primary, sub), i.e. sub << 10 | primary
+    let mut ret = format!("LANGUAGE 0x{:x}, 0x{:x}\nSTRINGTABLE\nBEGIN\n", langid & 0x3FF, langid >> 10);
+    for &(id, text) in neutral {
+        let text = translations.iter().find(|&&(t, _)| t == id).map(|&(_, t)| t).unwrap_or(text);
+        // Literal line breaks aren't allowed in strings, but escapes are
+        ret.push_str(&format!("    {}, {}\n", id, rc_quote(text).replace('\n', "\\n").replace('\r', "\\r").replace('\t', "\\t")));
+    }
+    ret.push_str("END\n");
+    ret
+}
+
+/// Generate, compile, and link resources read from the specified files, for example for embedded web content.
+///
+/// This is equivalent to compiling a resource script consisting of a `name TYPE "path"` line for each entry.
+/// String names and custom types must be identifiers (letters, digits, and underscores, not starting with a digit),
+/// and, as with all resource names, are case-insensitive.
+///
+/// This emits `cargo:rerun-if-changed` for each file.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::{ResourceType, ResourceId};
+///
+/// fn main() {
+///     embed_resource::compile_resource_entries(&[
+///         (ResourceType::Icon, ResourceId::Ordinal(1), "res/app.ico"),
+///         (ResourceType::Html, ResourceId::Name("ABOUT".to_string()), "res/about.html"),
+///         (ResourceType::Custom("LICENSE"), ResourceId::Ordinal(1), "LICENSE"),
+///     ]).unwrap();
+/// }
+/// ```
+pub fn compile_resource_entries<P: AsRef<Path>>(entries: &[(ResourceType, ResourceId, P)]) -> Result<Option<Artifact>, CompileError> {
+    compile_resource_entries_with(entries, Options::new())
+}
+
+/// Like [`compile_resource_entries()`](fn.compile_resource_entries.html), but with the specified options,
+/// for example to declare the resources in a specific [language](struct.Options.html#method.language).
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::{ResourceType, ResourceId, Options};
+/// use std::path::Path;
+///
+/// fn main() {
+///     // German (Germany)
+///     embed_resource::compile_resource_entries_with(&[(ResourceType::Icon, ResourceId::Ordinal(1), Path::new("res/app.de.ico"))],
+///                                                   Options::new().language(0x0407)).unwrap();
+/// }
+/// ```
+pub fn compile_resource_entries_with<P: AsRef<Path>>(entries: &[(ResourceType, ResourceId, P)], options: Options) -> Result<Option<Artifact>, CompileError> {
+    let mut script = String::new();
+    for &(resource_type, ref name, ref path) in entries {
+        script.push_str(&entry_line(resource_type, name, &options.expand_path(path.as_ref())?)?);
+    }
+
+    compile_generated("embed_resource_entries", &script, options)
+}
+
+/// Generate, compile, and link the specified data as a resource of the specified type and name,
+/// for resources generated by the build script itself, like a rendered icon, without it needing to write any files.
+///
+/// The data is written to `$OUT_DIR/embed-resource-data/{hash}.bin`, named after a hash of its contents, or under the [temporary directory](struct.Options.html#method.temp_dir) with [`compile_bytes_with()`](fn.compile_bytes_with.html),
+/// and the generated resource script is [cached](struct.Options.html#method.cache),
+/// so the resource is only recompiled when the data changes.
+/// The data must be in the format the type calls for, like that of an `.ico` file for `ResourceType::Icon`.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::{ResourceType, ResourceId};
+///
+/// fn main() {
+///     let build_info = format!("{} built on {}", env!("CARGO_PKG_VERSION"), "Tuesday");
+///     embed_resource::compile_bytes(ResourceType::RcData, ResourceId::Name("BUILD_INFO".to_string()), build_info.as_bytes()).unwrap();
+/// }
+/// ```
+pub fn compile_bytes(resource_type: ResourceType, id: ResourceId, data: &[u8]) -> Result<Option<Artifact>, CompileError> {
+    compile_bytes_with(resource_type, id, data, Options::new())
+}
+
+/// Like [`compile_bytes()`](fn.compile_bytes.html), but with the specified [options](index.html#generated-resources).
+///
+/// The generated script is always [cached](struct.Options.html#method.cache).
+pub fn compile_bytes_with(resource_type: ResourceType, id: ResourceId, data: &[u8], options: Options) -> Result<Option<Artifact>, CompileError> {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    let data_dir = generated_dir(&options)?.join("embed-resource-data");
+    let data_path = data_dir.join(format!("{:016x}.bin", hasher.finish()));
+    if !data_path.is_file() {
+        fs::create_dir_all(&data_dir).and_then(|_| fs::write(&data_path, data)).map_err(|e| CompileError::Io(data_path.clone(), e))?;
+    }
+
+    let script = resource_line(type_keyword(resource_type)?, &id, &data_path)?;
+    compile_generated(&format!("embed_resource_bytes_{}_{}", type_keyword(resource_type)?, id).to_lowercase(),
+                      &script,
+                      options.cache(true))
+}
+
+/// Generate, compile, and link the specified XML file as a resource of the specified custom type and name,
+/// for example for configuration loaded with `FindResource()`.
+///
+/// The file is embedded as-is, like with [`compile_resource_entries()`](fn.compile_resource_entries.html),
+/// after checking that it's well-formed: that it has a single root element, and balanced tags.
+/// This emits `cargo:rerun-if-changed` for it.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::ResourceId;
+///
+/// fn main() {
+///     embed_resource::compile_xml("CONFIG", ResourceId::Name("DEFAULTS".to_string()), "res/defaults.xml").unwrap();
+/// }
+/// ```
+pub fn compile_xml<P: AsRef<Path>>(type_name: &str, id: ResourceId, xml_path: P) -> Result<Option<Artifact>, CompileError> {
+    compile_xml_with(type_name, id, xml_path, Options::new())
+}
+
+/// Like [`compile_xml()`](fn.compile_xml.html), but with the specified [options](index.html#generated-resources).
+pub fn compile_xml_with<P: AsRef<Path>>(type_name: &str, id: ResourceId, xml_path: P, options: Options) -> Result<Option<Artifact>, CompileError> {
+    let xml_path = &options.expand_path(xml_path.as_ref())?;
+    let xml = fs::read(xml_path).map_err(|e| CompileError::Io(xml_path.to_path_buf(), e))?;
+    check_well_formed(&String::from_utf8_lossy(&xml)).map_err(|e| CompileError::MalformedXml(xml_path.to_path_buf(), e))?;
+
+    let script = entry_line(ResourceType::Custom(type_name), &id, xml_path)?;
+    compile_generated(&format!("embed_resource_xml_{}_{}", type_name, id).to_lowercase(), &script, options)
+}
+
+/// Generate, compile, and link the specified BMP file as a `BITMAP` resource with the specified name, for `LoadBitmap()`,
+/// for example for a splash screen.
+///
+/// The file is checked to be a BMP first, since the resource compiler's errors for anything else are rather cryptic:
+/// it must start with the `BM` magic, followed by a file header pointing within the file, and a known `BITMAPINFOHEADER` variant.
+/// This emits `cargo:rerun-if-changed` for it.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::ResourceId;
+///
+/// fn main() {
+///     embed_resource::compile_bitmap(ResourceId::Name("SPLASH".to_string()), "res/splash.bmp").unwrap();
+/// }
+/// ```
+pub fn compile_bitmap<P: AsRef<Path>>(id: ResourceId, bmp_path: P) -> Result<Option<Artifact>, CompileError> {
+    compile_bitmap_with(id, bmp_path, Options::new())
+}
+
+/// Like [`compile_bitmap()`](fn.compile_bitmap.html), but with the specified [options](index.html#generated-resources).
+pub fn compile_bitmap_with<P: AsRef<Path>>(id: ResourceId, bmp_path: P, options: Options) -> Result<Option<Artifact>, CompileError> {
+    let bmp_path = &options.expand_path(bmp_path.as_ref())?;
+    let bmp = fs::read(bmp_path).map_err(|e| CompileError::Io(bmp_path.to_path_buf(), e))?;
+    check_bitmap(&bmp).map_err(|e| CompileError::InvalidBitmap(bmp_path.to_path_buf(), e))?;
+
+    let script = entry_line(ResourceType::Bitmap, &id, bmp_path)?;
+    compile_generated(&format!("embed_resource_bitmap_{}", id).to_lowercase(), &script, options)
+}
+
+/// Generate, compile, and link the specified cursor file as a resource with the specified name, for `LoadCursor()`.
+///
+/// Static cursors (`.cur`) are embedded with a `CURSOR` statement, as `RT_GROUP_CURSOR` and its `RT_CURSOR` images;
+/// animated cursors (`.ani`) as `RT_ANICURSOR`, by number, since not all resource compilers have a keyword for it,
+/// which `LoadCursor()` loads just the same.
+/// The file is checked to be one of these first, by its contents rather than its extension:
+/// a `.cur` must have a cursor (not icon) directory with at least one image, all within the file,
+/// and an `.ani` must be a RIFF `ACON` file no shorter than its header says.
+/// This emits `cargo:rerun-if-changed` for it.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::ResourceId;
+///
+/// fn main() {
+///     embed_resource::compile_cursor(ResourceId::Name("PEN".to_string()), "res/pen.cur").unwrap();
+///     embed_resource::compile_cursor(ResourceId::Name("BUSY".to_string()), "res/busy.ani").unwrap();
+/// }
+/// ```
+pub fn compile_cursor<P: AsRef<Path>>(id: ResourceId, cur_path: P) -> Result<Option<Artifact>, CompileError> {
+    compile_cursor_with(id, cur_path, Options::new())
+}
+
+/// Like [`compile_cursor()`](fn.compile_cursor.html), but with the specified [options](index.html#generated-resources).
+pub fn compile_cursor_with<P: AsRef<Path>>(id: ResourceId, cur_path: P, options: Options) -> Result<Option<Artifact>, CompileError> {
+    let cur_path = &options.expand_path(cur_path.as_ref())?;
+    let cur = fs::read(cur_path).map_err(|e| CompileError::Io(cur_path.to_path_buf(), e))?;
+    let animated = check_cursor(&cur).map_err(|e| CompileError::InvalidCursor(cur_path.to_path_buf(), e))?;
+
+    // 21 is RT_ANICURSOR
+    let script = keyword_entry_line(if animated { "21" } else { "CURSOR" }, &id, cur_path)?;
+    compile_generated(&format!("embed_resource_cursor_{}", id).to_lowercase(), &script, options)
+}
+
+/// Whether the file is an animated cursor, after checking it's a RIFF `ACON` file,
+/// or else a static one, an `ICONDIR` of type 2 followed by its entries, whose images are within the file
+fn check_cursor(cur: &[u8]) -> Result<bool, String> {
+    let u16_at = |i: usize| u16::from(cur[i]) | u16::from(cur[i + 1]) << 8;
+    let u32_at = |i: usize| u32::from(cur[i]) | u32::from(cur[i + 1]) << 8 | u32::from(cur[i + 2]) << 16 | u32::from(cur[i + 3]) << 24;
+
+    if cur.starts_with(b"RIFF") {
+        if cur.len() < 12 || &cur[8..12] != b"ACON" {
+            return Err("RIFF file, but not an animated cursor".to_string());
+        }
+        let riff_size = u32_at(4) as usize;
+        if riff_size + 8 > cur.len() {
+            return Err(format!("truncated animated cursor, {} of {} bytes", cur.len(), riff_size + 8));
+        }
+        return Ok(true);
+    }
+
+    if cur.len() < 6 || u16_at(0) != 0 {
+        return Err("no cursor directory".to_string());
+    }
+    match u16_at(2) {
+        2 => {}
+        1 => return Err("an icon, not a cursor".to_string()),
+        t => return Err(format!("unknown image type {}", t)),
+    }
+    let count = u16_at(4) as usize;
+    if count == 0 {
+        return Err("no images".to_string());
+    }
+    if cur.len() < 6 + 16 * count {
+        return Err(format!("truncated directory of {} images, only {} bytes", count, cur.len()));
+    }
+    for i in 0..count {
+        let (size, offset) = (u32_at(6 + 16 * i + 8) as usize, u32_at(6 + 16 * i + 12) as usize);
+        if offset < 6 + 16 * count || offset + size > cur.len() {
+            return Err(format!("image {} at {}+{} outside of the {}-byte file", i, offset, size, cur.len()));
+        }
+    }
+    Ok(false)
+}
+
+/// A `BITMAPFILEHEADER`, then the size of the `BITMAPCOREHEADER`, `BITMAPINFOHEADER`, &c., and the pixels at the specified offset
+fn check_bitmap(bmp: &[u8]) -> Result<(), String> {
+    let u32_at = |i: usize| u32::from(bmp[i]) | u32::from(bmp[i + 1]) << 8 | u32::from(bmp[i + 2]) << 16 | u32::from(bmp[i + 3]) << 24;
+
+    if !bmp.starts_with(b"BM") {
+        return Err("no BM magic".to_string());
+    }
+    if bmp.len() < 14 + 4 {
+        return Err(format!("truncated header, only {} bytes", bmp.len()));
+    }
+    let header_size = u32_at(14);
+    if ![12, 40, 52, 56, 64, 108, 124].contains(&header_size) {
+        return Err(format!("unknown bitmap header size {}", header_size));
+    }
+    let pixels_offset = u32_at(10);
+    if (pixels_offset as usize) < 14 + header_size as usize || pixels_offset as usize > bmp.len() {
+        return Err(format!("pixel data offset {} outside of the {}-byte file", pixels_offset, bmp.len()));
+    }
+    Ok(())
+}
+
+/// A `name TYPE "path"` line, emitting `cargo:rerun-if-changed` for the path
+fn entry_line(resource_type: ResourceType, name: &ResourceId, path: &Path) -> Result<String, CompileError> {
+    keyword_entry_line(type_keyword(resource_type)?, name, path)
+}
+
+/// Likewise, but for a type specified as it's to be written in the script
+fn keyword_entry_line(keyword: &str, name: &ResourceId, path: &Path) -> Result<String, CompileError> {
+    let path = absolute_path(path);
+    let line = resource_line(keyword, name, &path)?;
+    println!("cargo:rerun-if-changed={}", path.display());
+    Ok(line)
+}
+
+/// A `name TYPE "path"` line
+fn resource_line(keyword: &str, name: &ResourceId, path: &Path) -> Result<String, CompileError> {
+    let name = match *name {
+        ResourceId::Ordinal(o) => o.to_string(),
+        ResourceId::Name(ref n) if is_identifier(n) => n.clone(),
+        ResourceId::Name(ref n) => return Err(CompileError::InvalidResourceName(n.clone())),
+    };
+    Ok(format!("{} {} {}\n", name, keyword, rc_string_literal(path)?))
+}
+
+fn type_keyword<'a>(resource_type: ResourceType<'a>) -> Result<&'a str, CompileError> {
+    match resource_type {
+        ResourceType::Html => Ok("HTML"),
+        ResourceType::RcData => Ok("RCDATA"),
+        ResourceType::Bitmap => Ok("BITMAP"),
+        ResourceType::Cursor => Ok("CURSOR"),
+        ResourceType::Icon => Ok("ICON"),
+        ResourceType::Custom(t) if is_identifier(t) => Ok(t),
+        ResourceType::Custom(t) => Err(CompileError::InvalidResourceName(t.to_string())),
+    }
+}
+
+/// Generate, compile, and link an accelerator table with the specified ID, for `LoadAccelerators()`,
+/// mapping keys with modifiers to `WM_COMMAND` IDs.
+///
+/// `VK_` constants need `windows.h`, which is `#include`d, and unknown ones are an error from the resource compiler.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::{AcceleratorKey, Modifiers};
+///
+/// fn main() {
+///     embed_resource::compile_accelerators(1, &[
+///         (AcceleratorKey::Virtual("S"), 100, Modifiers::CONTROL),
+///         (AcceleratorKey::Virtual("S"), 101, Modifiers::CONTROL | Modifiers::SHIFT),
+///         (AcceleratorKey::Virtual("VK_F5"), 102, Modifiers::NONE),
+///         (AcceleratorKey::Char('?'), 103, Modifiers::NONE),
+///     ]).unwrap();
+/// }
+/// ```
+pub fn compile_accelerators(id: u16, entries: &[(AcceleratorKey, u16, Modifiers)]) -> Result<Option<Artifact>, CompileError> {
+    compile_accelerators_with(id, entries, Options::new())
+}
+
+/// Like [`compile_accelerators()`](fn.compile_accelerators.html), but with the specified [options](index.html#generated-resources).
+pub fn compile_accelerators_with(id: u16, entries: &[(AcceleratorKey, u16, Modifiers)], options: Options) -> Result<Option<Artifact>, CompileError> {
+    let script = format!("#include <windows.h>\n{}", accelerators_script(id, entries)?);
+    compile_generated(&format!("embed_resource_accelerators_{}", id), &script, options)
+}
+
+/// Generate, compile, and link a dialog box template with the specified ID, for `DialogBox()` & co.
+///
+/// See [`DialogSpec::to_rc()`](struct.DialogSpec.html#method.to_rc) for the validation done.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::{ControlClass, DialogControl, DialogSpec};
+///
+/// fn main() {
+///     embed_resource::compile_dialog(101, &DialogSpec::new(186, 64)
+///         .caption("Enter password")
+///         .style("DS_CENTER")
+///         .control(DialogControl::new(ControlClass::Static, 0xFFFF, "Password:", (7, 9, 40, 8)))
+///         .control(DialogControl::new(ControlClass::Edit, 1000, "", (50, 7, 129, 14)).style("ES_PASSWORD"))
+///         .control(DialogControl::new(ControlClass::Button, 1, "OK", (75, 43, 50, 14)).style("BS_DEFPUSHBUTTON"))
+///         .control(DialogControl::new(ControlClass::Button, 2, "Cancel", (129, 43, 50, 14))))
+///         .unwrap();
+/// }
+/// ```
+pub fn compile_dialog(id: u16, dialog: &DialogSpec) -> Result<Option<Artifact>, CompileError> {
+    compile_dialog_with(id, dialog, Options::new())
+}
+
+/// Like [`compile_dialog()`](fn.compile_dialog.html), but with the specified [options](index.html#generated-resources).
+pub fn compile_dialog_with(id: u16, dialog: &DialogSpec, options: Options) -> Result<Option<Artifact>, CompileError> {
+    let script = format!("#include <windows.h>\n{}", dialog.to_rc(id)?);
+    compile_generated(&format!("embed_resource_dialog_{}", id), &script, options)
+}
+
+/// Generate, compile, and link a type library resource, for COM servers.
+///
+/// This is equivalent to compiling a resource script consisting of `id TYPELIB "tlb_path"`;
+/// `LoadTypeLib()` and self-registration expect the type library to have ID 1.
+///
+/// This emits `cargo:rerun-if-changed` for the type library, see [generated resources](index.html#generated-resources).
+///
+/// # Examples
+///
+/// In your build script, having generated `$OUT_DIR/server.tlb` with `MIDL.EXE`:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// # use std::env;
+/// # use std::path::Path;
+///
+/// fn main() {
+///     embed_resource::compile_typelib(Path::new(&env::var("OUT_DIR").unwrap()).join("server.tlb"), 1).unwrap();
+/// }
+/// ```
+pub fn compile_typelib<P: AsRef<Path>>(tlb_path: P, id: u16) -> Result<Option<Artifact>, CompileError> {
+    compile_typelib_with(tlb_path, id, Options::new())
+}
+
+/// Like [`compile_typelib()`](fn.compile_typelib.html), but with the specified [options](index.html#generated-resources).
+pub fn compile_typelib_with<P: AsRef<Path>>(tlb_path: P, id: u16, options: Options) -> Result<Option<Artifact>, CompileError> {
+    let tlb_path = absolute_path(&options.expand_path(tlb_path.as_ref())?);
+    println!("cargo:rerun-if-changed={}", tlb_path.display());
+
+    compile_generated(&format!("embed_resource_typelib_{}", id), &format!("{} TYPELIB {}\n", id, rc_string_literal(&tlb_path)?), options)
+}
+
+
+/// Write the generated resource script to `{generated_dir}/{name}.rc` in the requested encoding and compile it as usual.
+fn compile_generated(name: &str, script: &str, options: Options) -> Result<Option<Artifact>, CompileError> {
+    let script_path = generated_dir(&options)?.join(format!("{}.rc", name));
+    let encoding = options.script_encoding.unwrap_or_else(|| ResourceCompiler::new(&env::var("TARGET").unwrap_or_default()).script_encoding());
+    let data = match encoding {
+        ScriptEncoding::Utf8 => script.as_bytes().to_vec(),
+        ScriptEncoding::Utf16 => "\u{FEFF}".encode_utf16().chain(script.encode_utf16()).flat_map(|c| c.to_le_bytes().to_vec()).collect(),
+    };
+    fs::write(&script_path, data).map_err(|e| CompileError::Io(script_path.clone(), e))?;
+
+    compile_impl(&script_path, options)
+}
+
+/// Where generated files go: the [temporary directory](struct.Options.html#method.temp_dir), created if needed, if specified, otherwise `$OUT_DIR`
+fn generated_dir(options: &Options) -> Result<PathBuf, CompileError> {
+    match options.temp_dir {
+        Some(ref dir) => {
+            fs::create_dir_all(dir).map_err(|e| CompileError::Io(dir.clone(), e))?;
+            Ok(dir.clone())
+        }
+        None => Ok(PathBuf::from(out_dir()?)),
+    }
+}
+
+fn find_scripts(dir: &Path, recursive: bool, into: &mut Vec<PathBuf>) -> Result<(), CompileError> {
+    for entry in fs::read_dir(dir).map_err(|e| CompileError::Io(dir.to_path_buf(), e))? {
+        let entry = entry.map_err(|e| CompileError::Io(dir.to_path_buf(), e))?;
+        let path = entry.path();
+        if path.is_dir() {
+            if recursive {
+                find_scripts(&path, recursive, into)?;
+            }
+        } else if path.extension().and_then(|e| e.to_str()).map(|e| e.eq_ignore_ascii_case("rc")).unwrap_or(false) {
+            into.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_identifier(s: &str) -> bool {
+    s.chars().next().map(|c| c.is_ascii_alphabetic() || c == '_').unwrap_or(false) && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Make a user-specified path absolute, since generated resource scripts live in `$OUT_DIR`, not next to the referenced file.
+fn absolute_path(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        env::current_dir().expect("Couldn't get current directory").join(path)
+    }
+}
+
+/// Quote a path for use as a file name in a resource script: backslashes are escaped and quotes are doubled.
+pub(crate) fn rc_string_literal(path: &Path) -> Result<String, CompileError> {
+    let path_str = path.to_str().ok_or_else(|| CompileError::InvalidPath(path.to_path_buf()))?;
+    Ok(rc_quote(path_str))
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::{rustflags_request_manifest, check_bitmap, check_cursor};
+
+    #[test]
+    fn rustflags_manifest() {
+        assert!(!rustflags_request_manifest(""));
+        assert!(!rustflags_request_manifest("-Ctarget-feature=+crt-static\x1F-Clink-arg=/MANIFEST:NO"));
+        assert!(!rustflags_request_manifest("-C\x1Flink-arg=/MANIFESTUAC:level='requireAdministrator'"));
+        assert!(rustflags_request_manifest("-Ctarget-feature=+crt-static\x1F-Clink-arg=/MANIFEST:EMBED"));
+        assert!(rustflags_request_manifest("-C\x1Flink-args=/DEBUG /manifest:embed,ID=2"));
+        assert!(rustflags_request_manifest("-Clink-arg=-MANIFEST"));
+    }
+
+    fn le32(v: u32) -> Vec<u8> {
+        vec![v as u8, (v >> 8) as u8, (v >> 16) as u8, (v >> 24) as u8]
+    }
+
+    /// A `BITMAPFILEHEADER` with the pixels at the specified offset, then a header of the specified size, followed by four bytes
+    fn bitmap(header_size: u32, pixels_offset: u32) -> Vec<u8> {
+        let mut ret = b"BM".to_vec();
+        ret.extend(le32(14 + header_size + 4));
+        ret.extend(le32(0));
+        ret.extend(le32(pixels_offset));
+        ret.extend(le32(header_size));
+        ret.resize(14 + header_size as usize + 4, 0);
+        ret
+    }
+
+    #[test]
+    fn bitmap_headers() {
+        for &header_size in &[12, 40, 52, 56, 64, 108, 124] {
+            assert_eq!(check_bitmap(&bitmap(header_size, 14 + header_size)), Ok(()));
+        }
+        assert_eq!(check_bitmap(&bitmap(40, 58)), Ok(()));
+        assert_eq!(check_bitmap(&bitmap(20, 34)), Err("unknown bitmap header size 20".to_string()));
+        assert_eq!(check_bitmap(&bitmap(0, 14)), Err("unknown bitmap header size 0".to_string()));
+    }
+
+    #[test]
+    fn bitmap_magic_and_truncation() {
+        let mut bmp = bitmap(40, 54);
+        bmp[1] = b'A';
+        assert_eq!(check_bitmap(&bmp), Err("no BM magic".to_string()));
+        assert_eq!(check_bitmap(b""), Err("no BM magic".to_string()));
+        assert_eq!(check_bitmap(b"B"), Err("no BM magic".to_string()));
+
+        let bmp = bitmap(40, 54);
+        assert_eq!(check_bitmap(&bmp[..2]), Err("truncated header, only 2 bytes".to_string()));
+        assert_eq!(check_bitmap(&bmp[..17]), Err("truncated header, only 17 bytes".to_string()));
+        assert_eq!(check_bitmap(&bmp[..18]), Err("pixel data offset 54 outside of the 18-byte file".to_string()));
+        assert_eq!(check_bitmap(&bmp[..54]), Ok(()));
+    }
+
+    #[test]
+    fn bitmap_offsets() {
+        assert_eq!(check_bitmap(&bitmap(40, 53)), Err("pixel data offset 53 outside of the 58-byte file".to_string()));
+        assert_eq!(check_bitmap(&bitmap(40, 58)), Ok(()));
+        assert_eq!(check_bitmap(&bitmap(40, 59)), Err("pixel data offset 59 outside of the 58-byte file".to_string()));
+        assert_eq!(check_bitmap(&bitmap(12, 0xFFFF_FFFF)), Err("pixel data offset 4294967295 outside of the 30-byte file".to_string()));
+    }
+
+    /// An `ICONDIR` of the specified type, with entries of the specified sizes and offsets, padded to the specified length
+    fn cursor(image_type: u16, images: &[(u32, u32)], len: usize) -> Vec<u8> {
+        let mut ret = vec![0, 0, image_type as u8, 0, images.len() as u8, 0];
+        for &(size, offset) in images {
+            ret.extend_from_slice(&[32, 32, 0, 0, 0, 0, 0, 0]);
+            ret.extend(le32(size));
+            ret.extend(le32(offset));
+        }
+        ret.resize(len, 0);
+        ret
+    }
+
+    /// A RIFF `ACON` file with the specified RIFF size, padded to the specified length
+    fn animated_cursor(riff_size: u32, len: usize) -> Vec<u8> {
+        let mut ret = b"RIFF".to_vec();
+        ret.extend(le32(riff_size));
+        ret.extend_from_slice(b"ACON");
+        ret.resize(len, 0);
+        ret
+    }
+
+    #[test]
+    fn cursor_static() {
+        assert_eq!(check_cursor(&cursor(2, &[(10, 22)], 32)), Ok(false));
+        assert_eq!(check_cursor(&cursor(2, &[(10, 38), (4, 48)], 52)), Ok(false));
+        assert_eq!(check_cursor(&cursor(1, &[(10, 22)], 32)), Err("an icon, not a cursor".to_string()));
+        assert_eq!(check_cursor(&cursor(3, &[(10, 22)], 32)), Err("unknown image type 3".to_string()));
+        assert_eq!(check_cursor(&cursor(2, &[], 6)), Err("no images".to_string()));
+
+        let mut cur = cursor(2, &[(10, 22)], 32);
+        cur[1] = 1;
+        assert_eq!(check_cursor(&cur), Err("no cursor directory".to_string()));
+        assert_eq!(check_cursor(b""), Err("no cursor directory".to_string()));
+        assert_eq!(check_cursor(&[0, 0, 2, 0, 1]), Err("no cursor directory".to_string()));
+    }
+
+    #[test]
+    fn cursor_truncated() {
+        let cur = cursor(2, &[(10, 38), (4, 48)], 52);
+        assert_eq!(check_cursor(&cur[..6]), Err("truncated directory of 2 images, only 6 bytes".to_string()));
+        assert_eq!(check_cursor(&cur[..37]), Err("truncated directory of 2 images, only 37 bytes".to_string()));
+        assert_eq!(check_cursor(&cur[..51]), Err("image 1 at 48+4 outside of the 51-byte file".to_string()));
+    }
+
+    #[test]
+    fn cursor_offsets() {
+        assert_eq!(check_cursor(&cursor(2, &[(10, 21)], 32)), Err("image 0 at 21+10 outside of the 32-byte file".to_string()));
+        assert_eq!(check_cursor(&cursor(2, &[(10, 23)], 32)), Err("image 0 at 23+10 outside of the 32-byte file".to_string()));
+        assert_eq!(check_cursor(&cursor(2, &[(10, 38), (4, 0)], 52)), Err("image 1 at 0+4 outside of the 52-byte file".to_string()));
+        assert_eq!(check_cursor(&cursor(2, &[(0xFFFF_FFFF, 22)], 32)), Err("image 0 at 22+4294967295 outside of the 32-byte file".to_string()));
+    }
+
+    #[test]
+    fn cursor_animated() {
+        assert_eq!(check_cursor(&animated_cursor(4, 12)), Ok(true));
+        assert_eq!(check_cursor(&animated_cursor(20, 28)), Ok(true));
+        assert_eq!(check_cursor(&animated_cursor(20, 27)), Err("truncated animated cursor, 27 of 28 bytes".to_string()));
+        assert_eq!(check_cursor(&animated_cursor(4, 12)[..11]), Err("RIFF file, but not an animated cursor".to_string()));
+
+        let mut ani = animated_cursor(20, 28);
+        ani[8..12].copy_from_slice(b"WAVE");
+        assert_eq!(check_cursor(&ani), Err("RIFF file, but not an animated cursor".to_string()));
+    }
+}
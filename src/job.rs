@@ -0,0 +1,95 @@
+use std::process::Child;
+use std::time::Duration;
+use std::io;
+
+#[cfg(target_os = "windows")]
+use self::windows::Job;
+#[cfg(not(target_os = "windows"))]
+use self::other::Job;
+
+
+/// Confine the child to its memory and CPU time limits until the returned job is dropped,
+/// killing it if it can't be
+pub fn confine(child: &mut Child, max_memory: Option<usize>, max_cpu_time: Option<Duration>) -> io::Result<Job> {
+    let job = Job::new(child, max_memory, max_cpu_time);
+    if job.is_err() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    job
+}
+
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use winapi::um::winnt::{HANDLE, JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_JOB_MEMORY, JOB_OBJECT_LIMIT_JOB_TIME,
+                            JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE, JobObjectExtendedLimitInformation};
+    use winapi::um::jobapi2::{AssignProcessToJobObject, CreateJobObjectW, SetInformationJobObject};
+    use winapi::um::handleapi::CloseHandle;
+    use std::os::windows::io::AsRawHandle;
+    use std::process::Child;
+    use std::time::Duration;
+    use std::{io, mem, ptr};
+
+
+    /// A job object, which kills the processes in it when closed, so also when this process exits
+    pub struct Job(HANDLE);
+
+    impl Job {
+        pub fn new(child: &Child, max_memory: Option<usize>, max_cpu_time: Option<Duration>) -> io::Result<Job> {
+            unsafe {
+                let job = CreateJobObjectW(ptr::null_mut(), ptr::null());
+                if job.is_null() {
+                    return Err(io::Error::last_os_error());
+                }
+                let job = Job(job);
+
+                let mut limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = mem::zeroed();
+                limits.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+                if let Some(max_memory) = max_memory {
+                    limits.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY;
+                    limits.JobMemoryLimit = max_memory;
+                }
+                if let Some(max_cpu_time) = max_cpu_time {
+                    // In 100ns ticks
+                    limits.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_JOB_TIME;
+                    *limits.BasicLimitInformation.PerJobUserTimeLimit.QuadPart_mut() = (max_cpu_time.as_nanos() / 100).min(std::i64::MAX as u128) as i64;
+                }
+
+                if SetInformationJobObject(job.0,
+                                           JobObjectExtendedLimitInformation,
+                                           &mut limits as *mut _ as *mut _,
+                                           mem::size_of_val(&limits) as u32) == 0 ||
+                   AssignProcessToJobObject(job.0, child.as_raw_handle() as HANDLE) == 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(job)
+            }
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod other {
+    use std::process::Child;
+    use std::time::Duration;
+    use std::io;
+
+
+    /// Only Windows has job objects, so there's nothing to confine the child with
+    pub struct Job;
+
+    impl Job {
+        pub fn new(_: &Child, _: Option<usize>, _: Option<Duration>) -> io::Result<Job> {
+            Ok(Job)
+        }
+    }
+}
@@ -49,6 +49,138 @@
 //! }
 //! ```
 //!
+//! # Generated resources
+//!
+//! Helpers like [`compile_manifest()`](fn.compile_manifest.html) and [`compile_version_info()`](fn.compile_version_info.html) generate
+//! a resource script, and any files it needs, in `$OUT_DIR`, and compile it like any other.
+//! Their `_with()` variants take [`Options`](struct.Options.html), which apply to the generated script,
+//! for example to write it to a [temporary directory](struct.Options.html#method.temp_dir) instead.
+//!
+//! The helpers embedding existing files, like [`compile_resource_entries()`](fn.compile_resource_entries.html) and [`compile_typelib()`](fn.compile_typelib.html),
+//! emit `cargo:rerun-if-changed` for them, regardless of [`Options::rerun_directives()`](struct.Options.html#method.rerun_directives),
+//! so, as with all such directives, the build script will no longer be rerun for changes to any other file
+//! unless it also emits `cargo:rerun-if-changed` for it.
+//!
+//! # Linking
+//!
+//! The compiled resource is only data: under MSVC it's a `.res` file, which `LINK.EXE` converts itself,
+//! and otherwise it's a COFF object without any code, so neither references the C runtime.
+//! It thus links the same whether the CRT is linked statically (`-C target-feature=+crt-static`) or dynamically,
+//! and can't cause `LNK4098`-style CRT mismatches.
+//!
+//! # Reproducibility
+//!
+//! Neither `RC.EXE` nor `windres` timestamp their output, and the paths in resource scripts, generated ones included,
+//! only locate files, so the compiled resource only depends on the resource script and the files it references.
+//! With [`Options::reproducible()`](struct.Options.html#method.reproducible):
+//!
+//!   * `LINK.EXE`, which timestamps the resource directory when converting the `.res` file for MSVC targets,
+//!     gets `/Brepro`, which stops it (`lld-link` never timestamps it),
+//!   * `$SOURCE_DATE_EPOCH` is defined for the resource script, if set.
+//!
+//! What can't be controlled is the resource script's own use of `__DATE__` and `__TIME__`, or of other inputs that vary between builds,
+//! and `LINK.EXE`s older than Visual Studio 2015's, which don't support `/Brepro`.
+//!
+//! # Resource crates
+//!
+//! Resources shared by several crates in a workspace can be compiled once by a crate of their own,
+//! whose build script [exports](struct.Options.html#method.export) the compiled resource instead of linking it,
+//! and linked by the build scripts of the crates depending on it with [`link_exported()`](fn.link_exported.html).
+//!
+//! Cargo only passes the metadata on from crates with a `links` key, which the dependents' build scripts then refer to it by,
+//! and only to the crates depending on them directly, so `shared-resources/Cargo.toml` needs:
+//!
+//! ```toml
+//! [package]
+//! name = "shared-resources"
+//! links = "shared-resources"
+//! build = "build.rs"
+//!
+//! [build-dependencies]
+//! embed-resource = "1.4"
+//! ```
+//!
+//! and an empty `src/lib.rs`, with `shared-resources/build.rs`:
+//!
+//! ```rust,no_run
+//! extern crate embed_resource;
+//! use embed_resource::Options;
+//!
+//! fn main() {
+//!     embed_resource::compile_with("shared.rc", Options::new().export(true)).unwrap();
+//! }
+//! ```
+//!
+//! and each dependent crate, with `shared-resources` in its `[dependencies]`, a build script like:
+//!
+//! ```rust,no_run
+//! extern crate embed_resource;
+//! use embed_resource::CrateType;
+//!
+//! fn main() {
+//!     embed_resource::link_exported("shared-resources", CrateType::Bin);
+//! }
+//! ```
+//!
+//! The compiled resource's path is also exported under the `resource-lib` key, for build scripts that don't depend on this crate
+//! or link it some other way, as `$DEP_SHARED_RESOURCES_RESOURCE_LIB` for the `links` key above.
+//! Exported as an [object](struct.Options.html#method.object), it can be passed to the linker as-is
+//! (unlike a `windres` archive, whose members linkers skip, since nothing references them):
+//!
+//! ```rust,no_run
+//! if let Ok(resource_lib) = std::env::var("DEP_SHARED_RESOURCES_RESOURCE_LIB") {
+//!     println!("cargo:rustc-link-arg-bins={}", resource_lib);
+//! }
+//! ```
+//!
+//! The repository's [`examples/shared-resources`](https://github.com/nabijaczleweli/rust-embed-resource/tree/master/examples/shared-resources)
+//! and [`examples/app`](https://github.com/nabijaczleweli/rust-embed-resource/tree/master/examples/app) are such a pair, built with
+//! `cargo build --manifest-path examples/app/Cargo.toml`.
+//!
+//! # Cross-compiling for MSVC
+//!
+//! On hosts other than Windows, `*-windows-msvc` targets are compiled for with `llvm-rc`, if `$EMBED_RESOURCE_WINDOWS_SDK`
+//! points at the Windows SDK and CRT headers, as downloaded by [`xwin`](https://github.com/Jake-Shadle/xwin)
+//! (`sdk/include/` and `crt/include/` in its splatted layout) or copied from a Windows machine (`Include/<version>/`);
+//! otherwise, nothing is compiled for them, so checking the target builds still works without the SDK.
+//!
+//! `llvm-rc` (and `llvm-cvtres`, for [objects](struct.Options.html#method.object)) are found next to the `clang-cl`, `clang`,
+//! or `lld-link` [linker](struct.Options.html#method.linker), with the same version suffix, like `llvm-rc-17` for `clang-cl-17`,
+//! or else in `$PATH`, and `llvm-rc` preprocesses with the `clang` it finds the same way.
+//! The SDK headers are passed as include directories, searched before any in `$INCLUDE`, which `llvm-rc` also reads,
+//! and the compiled `.res` file is linked as-is, as with `RC.EXE`.
+//!
+//! # Features
+//!
+//! The default `vswhom` feature enables the last-resort search for `RC.EXE` with the `vswhom` crate, which needs a C compiler;
+//! with `default-features = false` only the registry is consulted.
+//! Neither `vswhom` nor `winreg` are built on hosts other than Windows with MSVC.
+//!
+//! # Debugging
+//!
+//! Set `EMBED_RESOURCE_DEBUG=1` to have the compiler invocations and any problems encountered while searching for it
+//! printed to the standard error stream, which cargo shows for build scripts with `cargo build -vv`.
+//!
+//! Set `EMBED_RESOURCE_TIMING=1` to have how long finding and running the compiler took printed as `cargo:warning`s,
+//! which cargo always shows; the durations are also available in the returned [`Artifact`](struct.Artifact.html).
+//!
+//! Set `EMBED_RESOURCE_FORCE=1` to recompile resources even if they're [cached](struct.Options.html#method.cache) and up to date.
+//!
+//! # Rerunning
+//!
+//! With [`Options::rerun_directives()`](struct.Options.html#method.rerun_directives) on, and [`Options::cargo_directives()`](struct.Options.html#method.cargo_directives) not off,
+//! compiling emits `cargo:rerun-if-env-changed` for the environment variables this crate reads that aren't cargo's own or `PATH`:
+//! `EMBED_RESOURCE_DEBUG`, `EMBED_RESOURCE_TIMING`, `EMBED_RESOURCE_FORCE`, `EMBED_RESOURCE_GIT_HASH`, `SOURCE_DATE_EPOCH`, the `EMBED_RESOURCE_DEFINE_` ones that are set,
+//! and, with MSVC, `WindowsSdkVerBinPath`, `WindowsSdkDir`, `WindowsSDKVersion`, `VCINSTALLDIR`, and `INCLUDE`, or, otherwise, `RUSTC_LINKER`,
+//! and, on hosts other than Windows, `EMBED_RESOURCE_WINDOWS_SDK`.
+//! Any `rerun-if` directive, even just `rerun-if-env-changed`, turns off cargo's default of rerunning build scripts when any file in the package changes,
+//! so `cargo:rerun-if-changed` is emitted for the resource script and the files it [depends on](struct.Options.html#method.cache), too;
+//! build scripts reading other files need to emit their own.
+//! None are emitted if the script, or a file it `#include`s, isn't UTF-8 or UTF-16LE, since its dependencies can't all be found then.
+//!
+//! They're off by default, keeping cargo's default, which also covers these files, as in versions up to 1.4.1,
+//! but not the environment variables.
+//!
 //! # Credit
 //!
 //! In chronological order:
@@ -74,11 +206,33 @@
 //!   * ThePhD
 
 
-#[cfg(all(target_os = "windows", target_env = "msvc"))]
+#[cfg(all(target_os = "windows", target_env = "msvc", feature = "vswhom"))]
 extern crate vswhom;
 #[cfg(all(target_os = "windows", target_env = "msvc"))]
 extern crate winreg;
+#[cfg(target_os = "windows")]
+extern crate winapi;
 
+mod arch;
+mod error;
+mod helpers;
+mod manifest;
+mod accelerators;
+mod dialog;
+mod version_info;
+mod artifact;
+mod diagnostics;
+mod compile_commands;
+mod options;
+mod env_effects;
+mod res;
+mod cache;
+mod script;
+mod job;
+mod discovery;
+mod cfg;
+mod combined;
+mod toolchain;
 #[cfg(not(target_os = "windows"))]
 mod non_windows;
 #[cfg(all(target_os = "windows", target_env = "msvc"))]
@@ -93,17 +247,52 @@ use self::windows_msvc::*;
 #[cfg(all(target_os = "windows", not(target_env = "msvc")))]
 use self::windows_not_msvc::*;
 
-use std::env;
+pub mod prelude;
+
+pub use self::manifest::{AssemblyIdentity, ExecutionLevel, ManifestError, DpiAwareness, SupportedOs, Subsystem, Manifest};
+pub use self::version_info::VersionInfo;
+pub use self::combined::{CombinedScript, ScriptSection};
+pub use self::accelerators::{AcceleratorKey, Modifiers};
+pub use self::dialog::{ControlClass, DialogControl, DialogSpec};
+pub use self::res::{CompiledResource, ResourceId, read_compiled_resources};
+pub use self::script::{ResourceEntry, parse_resource_ids, parse_resource_script};
+pub use self::diagnostics::{Diagnostic, Severity, parse_diagnostics};
+pub use self::helpers::{ResourceType, compile_dir, compile_cfg, compile_resource_entries, compile_resource_entries_with, compile_xml, compile_xml_with, compile_bitmap, compile_bitmap_with,
+                        compile_cursor, compile_cursor_with, compile_bytes, compile_bytes_with, compile_accelerators, compile_accelerators_with, compile_dialog, compile_dialog_with,
+                        compile_manifest, compile_manifest_with, compile_manifest_fragments, compile_uac_manifest, compile_utf8_manifest, compile_combined, compile_version_info,
+                        compile_version_info_with, compile_localized, compile_localized_with, compile_localized_strings, compile_localized_strings_with, compile_typelib,
+                        compile_typelib_with};
+pub use self::env_effects::{EnvEffect, environment_effects};
+pub use self::cache::{CacheStatus, CacheMiss};
+pub use self::discovery::{DiscoveryStrategy, set_discovery_callback, clear_discovery_callback};
+pub use self::options::{ESSENTIAL_ENV_VARS, ScriptEncoding, OutputMode, CrateType, Options};
+pub use self::error::CompileError;
+pub use self::artifact::Artifact;
+pub use self::arch::{Arch, detect_arch};
+use self::options::Symbol;
+use self::helpers::rc_string_literal;
+use self::discovery::probed;
+
 use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output, Stdio};
+use std::time::{Duration, Instant};
+use std::thread;
+use std::{env, fmt, fs, io};
+use std::io::Read;
+use std::ffi::OsString;
+use std::sync::atomic::{AtomicPtr, Ordering::SeqCst};
+use std::ptr;
 
 
 /// Compile the Windows resource file and update the cargo search path if we're on Windows.
 ///
 /// On non-Windows non-Windows-cross-compile-target this does nothing, on non-MSVC Windows and Windows cross-compile targets,
 /// this chains `windres` with `ar`,
-/// but on MSVC Windows, this will try its hardest to find `RC.EXE` in Windows Kits and/or SDK directories,
+/// but on MSVC Windows, this will try its hardest to find `RC.EXE` in the Windows SDK set up by a Developer Command Prompt,
+/// if any, then in Windows Kits and/or SDK directories,
 /// falling back to [Jon Blow's VS discovery script](https://pastebin.com/3YvWQa5c),
-/// and on Windows 10 `%INCLUDE%` will be updated to help `RC.EXE` find `windows.h` and friends.
+/// and on Windows 10 `%INCLUDE%` will be updated to help `RC.EXE` find `windows.h` and friends, unless it has them already;
+/// if `RC.EXE` fails and they're nowhere to be found, a `cargo:warning` names the SDK header directories that are missing.
 ///
 /// # Examples
 ///
@@ -119,18 +308,912 @@ use std::path::{Path, PathBuf};
 /// ```
 #[inline]
 pub fn compile<T: AsRef<Path>>(resource_file: T) {
-    compile_impl(resource_file.as_ref())
+    let resource_file = resource_file.as_ref();
+    if let Err(err) = compile_impl(resource_file, Options::new()) {
+        panic!("Couldn't compile \"{}\": {}", resource_file.display(), err);
+    }
 }
 
-fn compile_impl(resource_file: &Path) {
-    let comp = ResourceCompiler::new();
-    if comp.is_supported() {
-        let prefix = &resource_file.file_stem().expect("resource_file has no stem").to_str().expect("resource_file's stem not UTF-8");
-        let out_dir = env::var("OUT_DIR").expect("No OUT_DIR env var");
+/// Like [`compile()`](fn.compile.html), but with the specified [`Options`](struct.Options.html),
+/// returning the compiled resource, or `None` if the target isn't Windows.
+///
+/// Preprocessor definitions are also read from `EMBED_RESOURCE_DEFINE_`-prefixed environment variables,
+/// so that, for example, `EMBED_RESOURCE_DEFINE_BUILD_ID=1234` is equivalent to `.define("BUILD_ID", Some("1234"))`.
+/// Definitions from [`Options::define()`](struct.Options.html#method.define) take precedence
+/// over environment variables defining the same symbol.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::Options;
+///
+/// fn main() {
+///     // Compile and link checksums.rc with VERSION_STRING defined
+///     embed_resource::compile_with("checksums.rc", Options::new().define("VERSION_STRING", Some("\"1.0\""))).unwrap();
+/// }
+/// ```
+#[inline]
+pub fn compile_with<T: AsRef<Path>>(resource_file: T, options: Options) -> Result<Option<Artifact>, CompileError> {
+    compile_impl(resource_file.as_ref(), options)
+}
+
+fn compile_impl(resource_file: &Path, options: Options) -> Result<Option<Artifact>, CompileError> {
+    // Not having TARGET is fine: non-Windows hosts will do nothing, and Windows hosts don't need it
+    let target = env::var("TARGET").unwrap_or_default();
+    if !ResourceCompiler::new(&target).is_supported() {
+        return Ok(None);
+    }
+
+    let out_dir = out_dir()?;
+    let (crate_type, suppress_directives, reproducible, export) =
+        (options.crate_type.unwrap_or_default(), options.suppress_directives.unwrap_or(false), options.reproducible.unwrap_or(false), options.export.unwrap_or(false));
+    let rerun_directives = !suppress_directives && options.rerun_directives.unwrap_or(false);
+    let exact_path = options.object.unwrap_or(false) || options.output_path.is_some();
+    let options = options.with_env_defines().with_env_linker().with_env_force();
+    // Any rerun-if directive turns off cargo's default of rerunning for any change in the package,
+    // so the script, unless generated, and what it depends on need to be tracked too,
+    // and, if not all of it can be found, because part of it can't be decoded, it's better to emit none
+    let rerun_if_changed: Option<Vec<PathBuf>> = if !rerun_directives {
+        None
+    } else {
+        let resource = options.resolve(resource_file)?;
+        script::complete_dependencies(&resource, &options.search_dirs().collect::<Vec<_>>()).map(|dependencies| {
+            Some(resource)
+                .into_iter()
+                .chain(dependencies)
+                .filter(|file| !file.starts_with(&out_dir) && !options.temp_dir.as_ref().map(|dir| file.starts_with(dir)).unwrap_or(false))
+                .collect()
+        })
+    };
+    if rerun_if_changed.is_none() && rerun_directives {
+        debug_log(format_args!("couldn't decode all of {} and what it includes, so not emitting rerun-if directives", resource_file.display()));
+    }
+    let artifact = compile_explicit(resource_file, Path::new(&out_dir), &target, options.clone()).map_err(|e| {
+        if let CompileError::CompilerFailed(..) | CompileError::AllCompilersFailed(..) = e {
+            warn_missing_sdk_headers(&ResourceCompiler::new(&target), &options);
+        }
+        e
+    })?;
+    if let Some(codepage) = artifact.detected_codepage {
+        println!("cargo:warning={} starts with a UTF-8 byte order mark, so it's compiled as UTF-8 (codepage {}); \
+                  specify Options::codepage() to silence this warning",
+                 resource_file.display(),
+                 codepage);
+    }
+    if let Some(rerun_if_changed) = rerun_if_changed {
+        for var in env_effects::tracked_env_vars() {
+            println!("cargo:rerun-if-env-changed={}", var);
+        }
+        for file in rerun_if_changed {
+            println!("cargo:rerun-if-changed={}", file.display());
+        }
+    }
+
+    if env_flag("EMBED_RESOURCE_TIMING") {
+        println!("cargo:warning=embed-resource: finding the compiler took {:?}, compiling {} took {:?}",
+                 artifact.discovery_time,
+                 resource_file.display(),
+                 artifact.compile_time);
+    }
+    if !suppress_directives && export {
+        println!("cargo:path={}", artifact.path.display());
+        println!("cargo:resource-lib={}", artifact.path.display());
+        println!("cargo:link_search={}", artifact.link_search.display());
+        println!("cargo:link_name={}", artifact.link_name);
+        if let Some(ref res_path) = artifact.res_path {
+            println!("cargo:res_path={}", res_path.display());
+        }
+    } else if !suppress_directives {
+        link_artifact(&artifact, crate_type, exact_path);
+        if reproducible && target.ends_with("-msvc") {
+            match crate_type {
+                CrateType::Bin => println!("cargo:rustc-link-arg=/Brepro"),
+                CrateType::Cdylib => println!("cargo:rustc-cdylib-link-arg=/Brepro"),
+            }
+        }
+    }
+    Ok(Some(artifact))
+}
 
-        comp.compile_resource(&out_dir, &prefix, resource_file.to_str().expect("resource_file not UTF-8"));
-        println!("cargo:rustc-link-search=native={}", out_dir);
-        println!("cargo:rustc-link-lib=dylib={}", prefix);
+/// Link the artifact by name, or, if it's an object or was written to a specific path, by path
+fn link_artifact(artifact: &Artifact, crate_type: CrateType, exact_path: bool) {
+    // Nothing references resources, so linkers would skip them in real archives
+    for directive in link_directives(artifact, crate_type, exact_path, is_thin_archive(&artifact.path)) {
+        println!("cargo:{}", directive);
+    }
+}
+
+/// The `cargo:` directives, without the prefix, linking the artifact for [`link_artifact()`](fn.link_artifact.html)
+fn link_directives(artifact: &Artifact, crate_type: CrateType, exact_path: bool, thin_archive: bool) -> Vec<String> {
+    let link_arg = match crate_type {
+        CrateType::Bin => "rustc-link-arg",
+        CrateType::Cdylib => "rustc-cdylib-link-arg",
+    };
+    match crate_type {
+        // The static:+whole-archive link kind would need Rust 1.61
+        _ if thin_archive => {
+            vec![format!("{}=-Wl,--whole-archive", link_arg),
+                 format!("{}={}", link_arg, artifact.path.display()),
+                 format!("{}=-Wl,--no-whole-archive", link_arg)]
+        }
+        CrateType::Bin if !exact_path => {
+            vec![format!("rustc-link-search=native={}", artifact.link_search.display()), format!("rustc-link-lib=dylib={}", artifact.link_name)]
+        }
+        _ => vec![format!("{}={}", link_arg, artifact.path.display())],
+    }
+}
+
+/// Whether the file is a GNU thin archive, made for [`Options::thin_archive()`](struct.Options.html#method.thin_archive)
+fn is_thin_archive(path: &Path) -> bool {
+    let mut magic = [0u8; 8];
+    fs::File::open(path).and_then(|mut f| f.read_exact(&mut magic)).is_ok() && &magic == b"!<thin>\n"
+}
+
+/// Make the thin archive referencing the object, or, if `ar` can't, fall back to the object itself, as without one
+fn make_thin_archive(object: &str, archive: &str, ar: &Path, arguments: &[OsString]) -> Result<(), CompileError> {
+    // ar would otherwise add to it, and fail if it's the object from a previous build without a thin archive
+    let _ = fs::remove_file(archive);
+    debug_log(format_args!("running {} {:?}", ar.display(), arguments));
+    if let Err(e) = run_compiler(ar, arguments, &Options::new().output(OutputMode::Silent)) {
+        debug_log(format_args!("{}, linking the object itself", e));
+        fs::copy(object, archive).map_err(|e| CompileError::Io(PathBuf::from(archive), e))?;
+    }
+    Ok(())
+}
+
+/// Link the resource [exported](struct.Options.html#method.export) by the build script of the dependency
+/// with the specified `links` name, as if it was compiled here with [`compile_with()`](fn.compile_with.html).
+///
+/// Returns `None` if the dependency didn't export anything, since it's not being built for Windows.
+/// See the [crate documentation](index.html#resource-crates) for an example.
+pub fn link_exported(links: &str, crate_type: CrateType) -> Option<Artifact> {
+    let var = |key: &str| env::var_os(format!("DEP_{}_{}", links.to_uppercase().replace('-', "_"), key));
+    let path = PathBuf::from(var("PATH")?);
+    let artifact = Artifact {
+        link_search: var("LINK_SEARCH").map(PathBuf::from).unwrap_or_else(|| path.parent().map(Path::to_path_buf).unwrap_or_default()),
+        link_name: var("LINK_NAME").and_then(|n| n.into_string().ok()).unwrap_or_default(),
+        res_path: var("RES_PATH").map(PathBuf::from),
+        copy_path: None,
+        res_copy_path: None,
+        resources: vec![],
+        detected_codepage: None,
+        stdout: String::new(),
+        stderr: String::new(),
+        compiler: None,
+        discovery_strategy: None,
+        discovery_time: Duration::default(),
+        compile_time: Duration::default(),
+        path,
+    };
+    // Objects and resources exported from a specific output path aren't named after link_name
+    let object = artifact.path.extension().map(|e| e == "o" || e == "obj").unwrap_or(false);
+    let named = artifact.path.file_stem().map(|s| *s == *artifact.link_name || *s == *format!("lib{}", artifact.link_name)).unwrap_or(false);
+    link_artifact(&artifact, crate_type, object || !named);
+    Some(artifact)
+}
+
+/// Compile the Windows resource file for the specified target triple into the specified directory,
+/// independently of the build script environment.
+///
+/// Unlike [`compile_with()`](fn.compile_with.html), this reads no environment variables
+/// (though the resource compiler itself still sees, for example, `%INCLUDE%`)
+/// and doesn't emit any `cargo:` directives; linking the returned artifact is up to the caller.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::Options;
+///
+/// fn main() {
+///     let artifact = embed_resource::compile_explicit("checksums.rc", "target/resources", "x86_64-pc-windows-msvc", Options::new()).unwrap();
+///     println!("Link {} from {}", artifact.link_name, artifact.link_search.display());
+/// }
+/// ```
+#[inline]
+pub fn compile_explicit<R: AsRef<Path>, O: AsRef<Path>>(resource: R, out_dir: O, target: &str, options: Options) -> Result<Artifact, CompileError> {
+    compile_explicit_impl(resource.as_ref(), out_dir.as_ref(), target, options)
+}
+
+fn compile_explicit_impl(resource: &Path, out_dir: &Path, target: &str, mut options: Options) -> Result<Artifact, CompileError> {
+    let comp = ResourceCompiler::new(target);
+    if !comp.is_supported() {
+        return Err(CompileError::UnsupportedTarget(target.to_string()));
+    }
+
+    if let Some(code) = options.suppressed_warnings.iter().find(|c| !is_warning_code(c)) {
+        return Err(CompileError::InvalidWarningCode(code.clone()));
+    }
+    if let Some(langid) = options.language.filter(|&l| !is_valid_langid(l)) {
+        return Err(CompileError::InvalidLanguage(langid));
+    }
+    if let Some(version) = options.sdk_include_version.as_ref().filter(|v| comp.sdk_include_dirs(v).is_none()) {
+        return Err(CompileError::MissingSdkIncludeVersion(version.clone()));
+    }
+
+    let (resource, detected_codepage) = resource_to_compile(resource, out_dir, &mut options)?;
+    let invocation = invocation(&comp, &resource, out_dir, &options)?;
+    if let Some(ref database) = options.compile_commands {
+        env::current_dir()
+            .and_then(|cwd| compile_commands::record(database, &cwd, &invocation.resource, &invocation.compiler, &invocation.arguments, &invocation.out_file))
+            .map_err(|e| CompileError::Io(database.clone(), e))?;
+    }
+    probe_writable(out_dir).map_err(|e| CompileError::OutputDirNotWritable(out_dir.to_path_buf(), e))?;
+    let link_search = match options.output_path {
+        Some(ref output_path) => {
+            let dir = output_path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+            fs::create_dir_all(dir)
+                .and_then(|_| probe_writable(dir))
+                .map_err(|e| CompileError::OutputDirNotWritable(dir.to_path_buf(), e))?;
+            dir.to_path_buf()
+        }
+        None => out_dir.to_path_buf(),
+    };
+
+    // Held until the fingerprint is stored, so builds sharing the output directory don't clobber each other's;
+    // if another build holds it, recompile instead of waiting for it
+    let cache_lock = if options.cache.unwrap_or(false) { cache::lock(Path::new(&invocation.out_file)) } else { None };
+    let (status, fingerprint) = if options.cache.unwrap_or(false) && cache_lock.is_none() {
+        (CacheStatus::Miss(CacheMiss::Locked), None)
+    } else {
+        invocation_cache_status(&invocation, &options)
+    };
+    let Invocation { prefix, out_file, res_file, mut compiler, arguments, mut discovery_strategy, discovery_time, thin_archive, .. } = invocation;
+    let fresh = status == CacheStatus::Hit;
+    let mut output = (String::new(), String::new());
+    let compile_start = Instant::now();
+    if fresh {
+        debug_log(format_args!("{} is up to date", out_file));
+    } else {
+        debug_log(format_args!("running {} {:?}", compiler.display(), arguments));
+        let (compiled, alternative) = run_compiler_or_alternatives(&compiler, comp.alternative_compilers(&options), &arguments, &options)
+            .map_err(|e| explain_not_found(&comp, discovery_strategy, e))?;
+        output = compiled;
+        if let Some(alternative) = alternative {
+            compiler = alternative;
+            discovery_strategy = DiscoveryStrategy::Path;
+        }
+        if let Some(ref res_file) = res_file {
+            // With the tools matching whichever compiler succeeded
+            match comp.res_to_output(&compiler, res_file, &out_file, &options) {
+                Some((converter, arguments)) => {
+                    debug_log(format_args!("running {} {:?}", converter.display(), arguments));
+                    run_compiler(&converter, &arguments, &Options::new().output(options.output.unwrap_or_default()))?;
+                }
+                None => {
+                    fs::copy(res_file, &out_file).map_err(|e| CompileError::Io(PathBuf::from(&out_file), e))?;
+                }
+            }
+        }
+        if let Some(ref fingerprint) = fingerprint {
+            cache::store(Path::new(&out_file), fingerprint).map_err(|e| CompileError::Io(PathBuf::from(&out_file), e))?;
+        }
+    }
+    let path = match thin_archive {
+        Some(archive) => {
+            match comp.thin_archive(&compiler, &out_file, &archive) {
+                Some((ref ar, ref arguments)) if !fresh || !is_thin_archive(Path::new(&archive)) => make_thin_archive(&out_file, &archive, ar, arguments)?,
+                _ => {}
+            }
+            archive
+        }
+        None => out_file.clone(),
+    };
+    drop(cache_lock);
+    let compile_time = compile_start.elapsed();
+    let mut resources = vec![];
+    if !options.expected_types.is_empty() || options.list_resources.unwrap_or(false) {
+        resources = read_compiled_resources(&out_file).map_err(|e| CompileError::Io(PathBuf::from(&out_file), e))?;
+        verify_resource_types(&resources, &options.expected_types)?;
+        if !options.list_resources.unwrap_or(false) {
+            resources.clear();
+        }
+    }
+
+    let (mut copy_path, mut res_copy_path) = (None, None);
+    if let Some(ref copy_to) = options.copy_to {
+        let copy_to = options.resolve(copy_to)?;
+        copy_path = Some(copy_atomically(Path::new(&path), &copy_to).map_err(|e| CompileError::Io(copy_to.clone(), e))?);
+        if let Some(ref res_file) = res_file {
+            res_copy_path = Some(copy_atomically(Path::new(res_file), &copy_to).map_err(|e| CompileError::Io(copy_to.clone(), e))?);
+        }
+    }
+
+    Ok(Artifact {
+        path: PathBuf::from(path),
+        link_search,
+        link_name: prefix,
+        res_path: res_file.map(PathBuf::from),
+        copy_path,
+        res_copy_path,
+        resources,
+        detected_codepage,
+        stdout: output.0,
+        stderr: output.1,
+        compiler: Some(compiler),
+        discovery_strategy: Some(discovery_strategy),
+        discovery_time,
+        compile_time,
+    })
+}
+
+/// Check whether compiling the resource file with [`compile_with()`](fn.compile_with.html) would reuse the previous output,
+/// as [cached](struct.Options.html#method.cache), or why not, without compiling it.
+///
+/// This does the same fingerprinting of the options, compiler invocation, and inputs,
+/// so it also finds the compiler, which, with MSVC, may update `%INCLUDE%`.
+///
+/// Returns `None` for non-Windows targets, which aren't compiled for at all.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::{CacheStatus, Options};
+///
+/// fn main() {
+///     match embed_resource::cache_status("checksums.rc", Options::new().cache(true)).unwrap() {
+///         Some(CacheStatus::Hit) => println!("cargo:warning=checksums.rc is up to date"),
+///         Some(CacheStatus::Miss(why)) => println!("cargo:warning=checksums.rc will be recompiled: {}", why),
+///         None => {}
+///     }
+/// }
+/// ```
+pub fn cache_status<P: AsRef<Path>>(resource: P, options: Options) -> Result<Option<CacheStatus>, CompileError> {
+    let target = env::var("TARGET").unwrap_or_default();
+    let comp = ResourceCompiler::new(&target);
+    if !comp.is_supported() {
+        return Ok(None);
+    }
+
+    let out_dir = out_dir()?;
+    let mut options = options.with_env_defines().with_env_linker().with_env_force();
+    let (resource, _) = resource_to_compile(resource.as_ref(), Path::new(&out_dir), &mut options)?;
+    let invocation = invocation(&comp, &resource, Path::new(&out_dir), &options)?;
+    Ok(Some(invocation_cache_status(&invocation, &options).0))
+}
+
+/// Where [`check()`](fn.check.html) writes the compiled resource
+#[cfg(target_os = "windows")]
+const NULL_DEVICE: &str = "NUL";
+#[cfg(not(target_os = "windows"))]
+const NULL_DEVICE: &str = "/dev/null";
+
+/// Compile the resource file for the specified target triple, only to check it, returning the compiler's diagnostics.
+///
+/// The output is discarded, written to `NUL` on Windows and `/dev/null` elsewhere, and, as with [`compile_explicit()`](fn.compile_explicit.html),
+/// no environment variables are read and no `cargo:` directives are printed, so this can be used outside of build scripts,
+/// for example in pre-commit hooks.
+/// Failing compilation isn't an error if it produced diagnostics, since they explain why.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::{Options, Severity};
+///
+/// fn main() {
+///     let diagnostics = embed_resource::check("checksums.rc", "x86_64-pc-windows-msvc", Options::new()).unwrap();
+///     for diagnostic in &diagnostics {
+///         println!("{}", diagnostic);
+///     }
+///     assert!(diagnostics.iter().all(|d| d.severity != Severity::Error));
+/// }
+/// ```
+pub fn check<P: AsRef<Path>>(resource: P, target: &str, options: Options) -> Result<Vec<Diagnostic>, CompileError> {
+    let resource = &options.resolve(resource.as_ref())?;
+    let comp = ResourceCompiler::new(target);
+    if !comp.is_supported() {
+        return Err(CompileError::UnsupportedTarget(target.to_string()));
+    }
+
+    let resource_str = resource.to_str().ok_or_else(|| CompileError::InvalidPath(resource.to_path_buf()))?;
+
+    let (compiler, _) = comp.compiler(&options);
+    let arguments = comp.arguments(resource_str, NULL_DEVICE, &options);
+    let output = run_command(compiler_command(&compiler, &arguments, &options), true, &options);
+
+    let output = output.map_err(|e| CompileError::Spawn(compiler.clone(), e))?;
+    let diagnostics: Vec<_> = parse_diagnostics(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .chain(parse_diagnostics(&String::from_utf8_lossy(&output.stderr)))
+        .filter(|d| !options.is_suppressed(d))
+        .collect();
+    if !output.status.success() && diagnostics.is_empty() {
+        return Err(CompileError::CompilerFailed(compiler, output.status));
+    }
+    Ok(diagnostics)
+}
+
+/// Check that the resource compiler for the target being built for can be found and run, by compiling a built-in resource script
+/// containing a single string table into a temporary directory, which is removed afterwards.
+///
+/// This goes through the same discovery, compilation, and verification as [`compile_explicit()`](fn.compile_explicit.html),
+/// with the target taken from `$TARGET`, which is optional on Windows hosts, as with [`compile()`](fn.compile.html).
+/// If compilation fails, the compiler's output is passed to the standard error stream.
+///
+/// # Examples
+///
+/// In a bug report reproducer:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+///
+/// fn main() {
+///     match embed_resource::verify_toolchain() {
+///         Ok(()) => println!("the resource compiler works"),
+///         Err(e) => println!("the resource compiler doesn't work: {}", e),
+///     }
+/// }
+/// ```
+pub fn verify_toolchain() -> Result<(), CompileError> {
+    let dir = env::temp_dir().join(format!("embed-resource-verify-{}", process::id()));
+    fs::create_dir_all(&dir).map_err(|e| CompileError::Io(dir.clone(), e))?;
+    let ret = verify_toolchain_in(&dir);
+    let _ = fs::remove_dir_all(&dir);
+    ret
+}
+
+fn verify_toolchain_in(dir: &Path) -> Result<(), CompileError> {
+    let script = dir.join("embed_resource_verify.rc");
+    fs::write(&script, "STRINGTABLE\nBEGIN\n    1, \"embed-resource\"\nEND\n").map_err(|e| CompileError::Io(script.clone(), e))?;
+
+    // 6 is RT_STRING
+    let options = Options::new().output(OutputMode::Captured).expect_resource_type(ResourceId::Ordinal(6));
+    compile_explicit(&script, dir, &env::var("TARGET").unwrap_or_default(), options).map(|_| ())
+}
+
+/// Find the resource compiler for the target being built for, and, with MSVC, add the Windows 10 SDK headers to `%INCLUDE%`, up front,
+/// returning the compiler and where it was found, or `None` if the target isn't Windows, like [`compile_with()`](fn.compile_with.html).
+///
+/// With MSVC, discovery is memoized for the rest of the build script, so the compilations that follow don't repeat it;
+/// this is idempotent, and needn't be followed by any.
+/// A compiler that wasn't found anywhere, not even in `$PATH`, is an error here, rather than when first running it:
+/// [`CompileError::MissingWindowsSdk`](enum.CompileError.html#variant.MissingWindowsSdk) if MSVC is installed without the Windows SDK.
+///
+/// # Examples
+///
+/// In your build script:
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+///
+/// fn main() {
+///     if let Some((compiler, strategy)) = embed_resource::warm_cache().unwrap() {
+///         println!("cargo:warning=compiling resources with {} ({})", compiler.display(), strategy);
+///     }
+///     embed_resource::compile_dir("resources", false).unwrap();
+/// }
+/// ```
+pub fn warm_cache() -> Result<Option<(PathBuf, DiscoveryStrategy)>, CompileError> {
+    let comp = ResourceCompiler::new(&env::var("TARGET").unwrap_or_default());
+    if !comp.is_supported() {
+        return Ok(None);
+    }
+
+    let (compiler, strategy) = comp.compiler(&Options::new().with_env_linker());
+    if strategy == DiscoveryStrategy::Path && !compile_commands::resolve_in_path(&compiler).is_file() {
+        let err = CompileError::Spawn(compiler, io::Error::new(io::ErrorKind::NotFound, "not found in PATH"));
+        return Err(explain_not_found(&comp, strategy, err));
+    }
+    Ok(Some((compiler, strategy)))
+}
+
+/// The name the compiled resource is linked as, and its output file named after:
+/// the resource file's stem, or `None` if it has none or it isn't UTF-8.
+///
+/// The output file is `{prefix}.lib` with MSVC and `lib{prefix}.a` otherwise.
+///
+/// # Examples
+///
+/// ```rust
+/// # use embed_resource::default_prefix;
+/// assert_eq!(default_prefix("res/checksums.rc"), Some("checksums".to_string()));
+/// assert_eq!(default_prefix(".."), None);
+/// ```
+pub fn default_prefix<P: AsRef<Path>>(resource: P) -> Option<String> {
+    resource.as_ref().file_stem().and_then(|s| s.to_str()).map(str::to_string)
+}
+
+/// The resource file resolved against the base directory, or, if filtering resource types, a filtered copy of it
+/// in the [temporary directory](struct.Options.html#method.temp_dir) or the output directory,
+/// in which case its directory is added to the front of the include path, so the files it references are still found,
+/// and the codepage detected from its byte order mark, if any, which it's then compiled in
+fn resource_to_compile(resource: &Path, out_dir: &Path, options: &mut Options) -> Result<(PathBuf, Option<u16>), CompileError> {
+    let resource = options.resolve(resource)?;
+    let detected_codepage = detect_codepage(&resource, options);
+    if let Some(codepage) = detected_codepage {
+        debug_log(format_args!("{} starts with a UTF-8 BOM, compiling with codepage {}", resource.display(), codepage));
+        options.codepage = Some(codepage);
+    }
+    let filter = !options.only_types.is_empty() || !options.skipped_types.is_empty();
+    if !filter && !options.embed_source.unwrap_or(false) {
+        return Ok((resource, detected_codepage));
+    }
+
+    let dir = options.temp_dir.as_ref().map(|d| d.as_path()).unwrap_or(out_dir).join("embed-resource-filtered");
+    fs::create_dir_all(&dir).map_err(|e| CompileError::Io(dir.clone(), e))?;
+    let filtered = dir.join(resource.file_name().ok_or_else(|| CompileError::InvalidPath(resource.clone()))?);
+    if filter {
+        script::filter_resources(&resource, &filtered, |t| options.keeps_type(t)).map_err(|e| CompileError::Io(resource.clone(), e))?;
+    } else {
+        fs::copy(&resource, &filtered).map_err(|e| CompileError::Io(resource.clone(), e))?;
+    }
+    if options.embed_source.unwrap_or(false) {
+        let prefix = default_prefix(&resource).ok_or_else(|| CompileError::InvalidPath(resource.clone()))?;
+        let mut name: String = prefix.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+        if name.starts_with(|c: char| c.is_ascii_digit()) {
+            name.insert(0, '_');
+        }
+        let source = env::current_dir().map(|cwd| cwd.join(&resource)).map_err(|e| CompileError::Io(resource.clone(), e))?;
+        let line = format!("{} EMBED_RESOURCE_SOURCE {}", name, rc_string_literal(&source)?);
+        script::append_line(&filtered, &line).map_err(|e| CompileError::Io(filtered.clone(), e))?;
+    }
+
+    let resource_dir = resource.parent().unwrap_or_else(|| Path::new(""));
+    let resource_dir = env::current_dir().map(|cwd| cwd.join(resource_dir)).map_err(|e| CompileError::Io(resource_dir.to_path_buf(), e))?;
+    options.include_dirs.insert(0, resource_dir);
+    Ok((filtered, detected_codepage))
+}
+
+/// UTF-8 if no codepage was specified and the resource file starts with a UTF-8 BOM
+fn detect_codepage(resource: &Path, options: &Options) -> Option<u16> {
+    if options.codepage.is_some() {
+        return None;
+    }
+    let mut bom = [0; 3];
+    let read = fs::File::open(resource).and_then(|mut f| f.read_exact(&mut bom));
+    if read.is_ok() && bom == [0xEF, 0xBB, 0xBF] { Some(65001) } else { None }
+}
+
+/// What compiling a resource file runs and produces
+struct Invocation {
+    resource: String,
+    prefix: String,
+    out_file: String,
+    res_file: Option<String>,
+    compiler: PathBuf,
+    arguments: Vec<OsString>,
+    discovery_strategy: DiscoveryStrategy,
+    discovery_time: Duration,
+    /// The archive to reference `out_file`, for [`Options::thin_archive()`](struct.Options.html#method.thin_archive)
+    thin_archive: Option<String>,
+}
+
+fn invocation(comp: &ResourceCompiler, resource: &Path, out_dir: &Path, options: &Options) -> Result<Invocation, CompileError> {
+    let prefix = default_prefix(resource).ok_or_else(|| CompileError::InvalidPath(resource.to_path_buf()))?;
+    let resource = resource.to_str().map(str::to_string).ok_or_else(|| CompileError::InvalidPath(resource.to_path_buf()))?;
+    let out_dir_str = out_dir.to_str().ok_or_else(|| CompileError::InvalidPath(out_dir.to_path_buf()))?;
+
+    let (mut object_file, mut output_file) = (comp.object_file(out_dir_str, &prefix), comp.output_file(out_dir_str, &prefix));
+    if let Some(ref output_path) = options.output_path {
+        let output_path = output_path.to_str().map(str::to_string).ok_or_else(|| CompileError::InvalidPath(output_path.clone()))?;
+        if options.object.unwrap_or(false) {
+            object_file = output_path;
+        } else {
+            output_file = output_path;
+        }
+    }
+    let discovery_start = Instant::now();
+    let (compiler, discovery_strategy) = comp.compiler(options);
+    let discovery_time = discovery_start.elapsed();
+    debug_log(format_args!("found {} ({}) in {:?}", compiler.display(), discovery_strategy, discovery_time));
+    let thin_archive = if options.thin_archive.unwrap_or(false) && !options.object.unwrap_or(false) {
+        comp.thin_archive(&compiler, &object_file, &output_file).map(|_| output_file.clone())
+    } else {
+        None
+    };
+    let out_file = if options.object.unwrap_or(false) || thin_archive.is_some() { object_file } else { output_file };
+    let res_file = if options.emit_res.unwrap_or(false) || (options.object.unwrap_or(false) && comp.object_needs_res()) {
+        Some(format!("{}/{}.res", out_dir_str, prefix))
+    } else {
+        None
+    };
+    let arguments = match res_file {
+        Some(ref res_file) => comp.res_arguments(&resource, res_file, options),
+        None => comp.arguments(&resource, &out_file, options),
+    };
+    Ok(Invocation {
+        resource,
+        prefix,
+        out_file,
+        res_file,
+        compiler,
+        arguments,
+        discovery_strategy,
+        discovery_time,
+        thin_archive,
+    })
+}
+
+/// Whether the outputs are up to date, and the fingerprint to store after compiling, if caching
+fn invocation_cache_status(invocation: &Invocation, options: &Options) -> (CacheStatus, Option<String>) {
+    if !options.cache.unwrap_or(false) {
+        return (CacheStatus::Miss(CacheMiss::Disabled), None);
+    }
+    // An unreadable dependency is fine: the compiler will report it;
+    // forcing doesn't affect the output, so the next unforced compilation can reuse it
+    let fingerprint = match cache::fingerprint(Path::new(&invocation.resource),
+                                               &invocation.compiler,
+                                               &invocation.arguments,
+                                               &Options { force: None, ..options.clone() }) {
+        Ok(fingerprint) => fingerprint,
+        Err(_) => return (CacheStatus::Miss(CacheMiss::UnreadableInput), None),
+    };
+
+    let status = match invocation.res_file {
+        _ if options.force.unwrap_or(false) => CacheStatus::Miss(CacheMiss::Forced),
+        Some(ref res_file) if !Path::new(res_file).is_file() => CacheStatus::Miss(CacheMiss::MissingOutput),
+        _ => cache::status(Path::new(&invocation.out_file), &fingerprint),
+    };
+    (status, Some(fingerprint))
+}
+
+/// The `<prefix>-windres` next to the `<prefix>-gcc` linker, if it exists, or in `$PATH` if the linker is just a name
+#[cfg(not(all(target_os = "windows", target_env = "msvc")))]
+fn windres_for_linker(linker: &Path) -> Option<PathBuf> {
+    let name = linker.file_name()?.to_str()?;
+    let name = name.trim_end_matches(".exe");
+    // Also x86_64-w64-mingw32-gcc-posix, x86_64-w64-mingw32-gcc-10, &c.
+    let prefix = &name[..name.rfind("-gcc")?];
+    let windres = format!("{}-windres{}", prefix, env::consts::EXE_SUFFIX);
+
+    match linker.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => {
+            let windres = dir.join(windres);
+            let exists = windres.is_file();
+            probed(DiscoveryStrategy::Linker, &windres, exists);
+            Some(windres).filter(|_| exists)
+        }
+        _ => env::var_os("PATH").and_then(|path| env::split_paths(&path).map(|dir| dir.join(&windres)).find(|w| w.is_file())),
+    }
+}
+
+/// The `<prefix>-ar` next to the `<prefix>-windres`, or in `$PATH` if it's just a name, like the latter
+#[cfg(not(all(target_os = "windows", target_env = "msvc")))]
+fn ar_for_windres(windres: &Path) -> PathBuf {
+    let name = windres.file_name().and_then(|n| n.to_str()).unwrap_or("windres");
+    let ar = match name.rfind("windres") {
+        Some(idx) => format!("{}ar{}", &name[..idx], &name[idx + "windres".len()..]),
+        None => format!("ar{}", env::consts::EXE_SUFFIX),
+    };
+    windres.with_file_name(ar)
+}
+
+/// `$OUT_DIR`, which cargo only sets for build scripts
+fn out_dir() -> Result<String, CompileError> {
+    env::var("OUT_DIR").map_err(|_| CompileError::MissingOutDir)
+}
+
+/// The resource compilers' own errors for unwritable outputs are rather obscure, so check beforehand
+fn probe_writable(dir: &Path) -> io::Result<()> {
+    let probe = dir.join(format!(".embed-resource-probe-{}", process::id()));
+    fs::File::create(&probe)?;
+    fs::remove_file(&probe)
+}
+
+/// Copy the file into the directory under the same name, through a temporary file renamed into place
+fn copy_atomically(file: &Path, dir: &Path) -> io::Result<PathBuf> {
+    let name = file.file_name().expect("compiled resources are files");
+    let destination = dir.join(name);
+    let temporary = dir.join(format!(".{}.{}.tmp", name.to_string_lossy(), process::id()));
+    fs::create_dir_all(dir)?;
+    if let Err(e) = fs::copy(file, &temporary).and_then(|_| fs::rename(&temporary, &destination)) {
+        let _ = fs::remove_file(&temporary);
+        return Err(e);
+    }
+    Ok(destination)
+}
+
+/// Print a message to the standard error stream if `$EMBED_RESOURCE_DEBUG` is set to something other than `0`.
+fn debug_log(args: fmt::Arguments) {
+    if env_flag("EMBED_RESOURCE_DEBUG") {
+        eprintln!("embed-resource: {}", args);
+    }
+}
+
+/// The value in the static, created on first use, since statics can't be initialised with a `Mutex` before Rust 1.63
+fn lazy<T, F: FnOnce() -> T>(cell: &'static AtomicPtr<T>, init: F) -> &'static T {
+    let mut value = cell.load(SeqCst);
+    if value.is_null() {
+        let new = Box::into_raw(Box::new(init()));
+        value = match cell.compare_exchange(ptr::null_mut(), new, SeqCst, SeqCst) {
+            Ok(_) => new,
+            // Another thread got there first
+            Err(existing) => {
+                drop(unsafe { Box::from_raw(new) });
+                existing
+            }
+        };
+    }
+    // Never freed, like any other static
+    unsafe { &*value }
+}
+
+/// Whether the environment variable is set to something other than `0`
+fn env_flag(var: &str) -> bool {
+    env::var_os(var).map(|v| !v.is_empty() && v != "0").unwrap_or(false)
+}
+
+/// Run the compiler, returning its output if it was captured
+fn run_compiler(compiler: &Path, arguments: &[OsString], options: &Options) -> Result<(String, String), CompileError> {
+    let (retries, backoff) = options.retry.unwrap_or_default();
+    let mut attempt = 0;
+    loop {
+        match run_compiler_once(compiler, arguments, options) {
+            Err((ref err, true)) if attempt < retries => {
+                let delay = backoff.checked_mul(2u32.saturating_pow(attempt)).unwrap_or(backoff);
+                debug_log(format_args!("{}, retrying in {:?}", err, delay));
+                thread::sleep(delay);
+                attempt += 1;
+            }
+            ret => return ret.map_err(|(err, _)| err),
+        }
+    }
+}
+
+/// A resource compiler that wasn't found anywhere, with MSVC installed, means the Windows SDK component wasn't,
+/// which is worth saying, since the bare "not found" reads like a discovery bug
+fn explain_not_found(comp: &ResourceCompiler, strategy: DiscoveryStrategy, err: CompileError) -> CompileError {
+    let not_found = match err {
+        CompileError::Spawn(_, ref e) => e.kind() == io::ErrorKind::NotFound,
+        _ => false,
+    };
+    if not_found && strategy == DiscoveryStrategy::Path {
+        comp.msvc_compiler().map(CompileError::MissingWindowsSdk).unwrap_or(err)
+    } else {
+        err
+    }
+}
+
+/// Run the compiler, then, if it fails and [alternatives](struct.Options.html#method.try_alternative_compilers) are to be tried,
+/// each of those in turn until one succeeds, returning the output and the alternative that succeeded, if any
+fn run_compiler_or_alternatives(compiler: &Path, alternatives: Vec<PathBuf>, arguments: &[OsString], options: &Options)
+                                -> Result<((String, String), Option<PathBuf>), CompileError> {
+    let mut errors = match run_compiler(compiler, arguments, options) {
+        Ok(output) => return Ok((output, None)),
+        Err(err) => {
+            if !options.try_alternatives.unwrap_or(false) {
+                return Err(err);
+            }
+            vec![err]
+        }
+    };
+    for alternative in alternatives.into_iter().filter(|a| a != compiler) {
+        debug_log(format_args!("{}, trying {}", errors[errors.len() - 1], alternative.display()));
+        match run_compiler(&alternative, arguments, options) {
+            Ok(output) => return Ok((output, Some(alternative))),
+            Err(err) => errors.push(err),
+        }
+    }
+    Err(CompileError::AllCompilersFailed(errors))
+}
+
+/// Run the compiler, returning its output if it was captured, or whether the failure looks transient
+fn run_compiler_once(compiler: &Path, arguments: &[OsString], options: &Options) -> Result<(String, String), (CompileError, bool)> {
+    let cmd = compiler_command(compiler, arguments, options);
+
+    let spawn_error = |e: io::Error| {
+        let transient = e.kind() != io::ErrorKind::NotFound;
+        (CompileError::Spawn(compiler.to_path_buf(), e), transient)
+    };
+    let (status, transient, output) = if options.output.unwrap_or_default() != OutputMode::Inherit || options.warnings_as_errors.unwrap_or(false) ||
+                                         options.retry.map(|(retries, _)| retries != 0).unwrap_or(false) ||
+                                         !options.suppressed_warnings.is_empty() {
+        // The output needs to be inspected, so pass it along afterwards, if requested;
+        // stdout isn't forwarded as-is so as not to be interpreted by cargo
+        let output = run_command(cmd, true, options).map_err(spawn_error)?;
+        let stdout = unsuppressed_lines(&String::from_utf8_lossy(&output.stdout), options);
+        let stderr = unsuppressed_lines(&String::from_utf8_lossy(&output.stderr), options);
+        match options.output.unwrap_or_default() {
+            OutputMode::Inherit => eprint!("{}{}", stdout, stderr),
+            OutputMode::Captured if !output.status.success() => eprint!("{}{}", stdout, stderr),
+            OutputMode::Captured | OutputMode::Silent => {}
+        }
+
+        let diagnostics: Vec<_> = parse_diagnostics(&stdout).into_iter().chain(parse_diagnostics(&stderr)).collect();
+        if output.status.success() && options.warnings_as_errors.unwrap_or(false) {
+            let warnings: Vec<_> = diagnostics.iter().filter(|d| d.severity == Severity::Warning).cloned().collect();
+            if !warnings.is_empty() {
+                return Err((CompileError::Warnings(warnings), false));
+            }
+        }
+        // Genuine compilation errors fail fast, but files locked by antivirus or indexers aren't the script's fault
+        let transient = (is_transient_message(&stdout) || is_transient_message(&stderr)) &&
+                        diagnostics.iter().filter(|d| d.severity == Severity::Error).all(|d| is_transient_message(&d.message));
+        let captured = if options.output.unwrap_or_default() == OutputMode::Captured { (stdout, stderr) } else { (String::new(), String::new()) };
+        (output.status, transient, captured)
+    } else {
+        (run_command(cmd, false, options).map_err(spawn_error)?.status, false, (String::new(), String::new()))
+    };
+
+    if status.success() {
+        Ok(output)
+    } else {
+        Err((CompileError::CompilerFailed(compiler.to_path_buf(), status), transient))
+    }
+}
+
+fn compiler_command(compiler: &Path, arguments: &[OsString], options: &Options) -> Command {
+    // The compiler and arguments are never joined into a command line here,
+    // so that compilers under, say, "C:\Program Files (x86)" don't need quoting
+    let mut cmd = Command::new(compiler);
+    cmd.args(arguments);
+    if options.minimal_environment.unwrap_or(false) {
+        cmd.env_clear();
+        for var in ESSENTIAL_ENV_VARS {
+            if let Some(value) = env::var_os(var) {
+                cmd.env(var, value);
+            }
+        }
+    }
+    cmd
+}
+
+/// Run the command to completion, capturing its output if requested, under the limits from `Options::limit_compiler()`
+fn run_command(mut cmd: Command, capture: bool, options: &Options) -> io::Result<Output> {
+    if capture {
+        cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    let mut child = cmd.spawn()?;
+    let _job = match options.compiler_limits {
+        Some((max_memory, max_cpu_time)) => Some(job::confine(&mut child, max_memory, max_cpu_time)?),
+        None => None,
+    };
+    child.wait_with_output()
+}
+
+/// The output without the lines reporting warnings suppressed with `Options::suppress_warning()`
+fn unsuppressed_lines(output: &str, options: &Options) -> String {
+    if options.suppressed_warnings.is_empty() {
+        return output.to_string();
+    }
+    let mut kept = String::with_capacity(output.len());
+    let mut rest = output;
+    while !rest.is_empty() {
+        let (line, tail) = rest.split_at(rest.find('\n').map(|i| i + 1).unwrap_or(rest.len()));
+        if !parse_diagnostics(line).iter().any(|d| options.is_suppressed(d)) {
+            kept.push_str(line);
+        }
+        rest = tail;
+    }
+    kept
+}
+
+/// `LANG_NEUTRAL` only goes with `SUBLANG_NEUTRAL`, the other sublanguages making runtime pseudo-languages
+fn is_valid_langid(langid: u16) -> bool {
+    langid & 0x3FF != 0 || langid == 0
+}
+
+/// `RC4005`, `rc2182`, &c.
+fn is_warning_code(code: &str) -> bool {
+    let digits = code.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    digits.len() != code.len() && !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_transient_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["being used by another process", "access is denied", "permission denied"].iter().any(|m| message.contains(m))
+}
+
+/// The classic "Unable to find windows.h" failure, when neither a Developer Command Prompt's `%INCLUDE%` nor the Windows 10 kits have the headers
+fn warn_missing_sdk_headers(comp: &ResourceCompiler, options: &Options) {
+    let missing = comp.missing_sdk_headers(options);
+    if !missing.is_empty() {
+        println!("cargo:warning=the Windows SDK headers weren't found in %INCLUDE% or the include directories, so windows.h can't be #included; \
+                  expected {}: install the Windows SDK (with the Visual Studio Installer's \"Windows 11 SDK\" component, for example), \
+                  or build from a Developer Command Prompt",
+                 missing.iter().map(|dir| dir.display().to_string()).collect::<Vec<_>>().join(" and "));
+    }
+}
+
+fn verify_resource_types(found: &[CompiledResource], expected_types: &[ResourceId]) -> Result<(), CompileError> {
+    let missing: Vec<_> = expected_types.iter().filter(|&t| !found.iter().any(|r| r.resource_type == *t)).cloned().collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(CompileError::MissingResourceTypes(missing))
     }
 }
 
@@ -147,7 +1230,7 @@ fn compile_impl(resource_file: &Path) {
 /// In your build script, find `midl.exe` and use it to compile an IDL file:
 ///
 /// ```rust,no_run
-/// # #[cfg(all(target_os = "windows", target_env = "msvc"))]
+/// # #[cfg(all(target_os = "windows", target_env = "msvc", feature = "vswhom"))]
 /// # {
 /// extern crate embed_resource;
 /// extern crate vswhom;
@@ -169,3 +1252,216 @@ fn compile_impl(resource_file: &Path) {
 pub fn find_windows_sdk_tool<T: AsRef<str>>(tool: T) -> Option<PathBuf> {
     find_windows_sdk_tool_impl(tool.as_ref())
 }
+
+/// Find an MSVC build tool for the specified target triple, like [`find_windows_sdk_tool()`](fn.find_windows_sdk_tool.html),
+/// but independently of the build script environment.
+///
+/// This is the same search `RC.EXE` is found with, so it works just as well for, say, the message compiler, `MC.EXE`.
+/// This always returns `None` on non-MSVC hosts.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use std::process::Command;
+///
+/// fn main() {
+///     if let Some(mc) = embed_resource::find_sdk_tool("mc.exe", "x86_64-pc-windows-msvc") {
+///         // Generates messages.rc and messages.h, the former of which can then be compiled as usual
+///         assert!(Command::new(mc).args(&["-h", "target", "-r", "target", "messages.mc"]).status().unwrap().success());
+///         embed_resource::compile("target/messages.rc");
+///     }
+/// }
+/// ```
+pub fn find_sdk_tool<T: AsRef<str>>(tool: T, target: &str) -> Option<PathBuf> {
+    find_sdk_tool_impl(tool.as_ref(), target)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Artifact, CrateType, link_directives, probe_writable};
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use std::{env, fs, io, process};
+
+    /// An empty directory for the test to build a tree in
+    pub fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("embed-resource-test-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn artifact() -> Artifact {
+        Artifact {
+            path: PathBuf::from("out/checksums.lib"),
+            link_search: PathBuf::from("out"),
+            link_name: "checksums".to_string(),
+            res_path: None,
+            copy_path: None,
+            res_copy_path: None,
+            resources: vec![],
+            detected_codepage: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            compiler: None,
+            discovery_strategy: None,
+            discovery_time: Duration::from_secs(0),
+            compile_time: Duration::from_secs(0),
+        }
+    }
+
+    #[test]
+    fn link_directives_bin() {
+        assert_eq!(link_directives(&artifact(), CrateType::Bin, false, false),
+                   ["rustc-link-search=native=out", "rustc-link-lib=dylib=checksums"]);
+        assert_eq!(link_directives(&artifact(), CrateType::Bin, true, false), ["rustc-link-arg=out/checksums.lib"]);
+    }
+
+    #[test]
+    fn link_directives_cdylib() {
+        assert_eq!(link_directives(&artifact(), CrateType::Cdylib, false, false), ["rustc-cdylib-link-arg=out/checksums.lib"]);
+        assert_eq!(link_directives(&artifact(), CrateType::Cdylib, true, false), ["rustc-cdylib-link-arg=out/checksums.lib"]);
+    }
+
+    #[test]
+    fn link_directives_thin_archive() {
+        assert_eq!(link_directives(&artifact(), CrateType::Bin, false, true),
+                   ["rustc-link-arg=-Wl,--whole-archive", "rustc-link-arg=out/checksums.lib", "rustc-link-arg=-Wl,--no-whole-archive"]);
+        assert_eq!(link_directives(&artifact(), CrateType::Cdylib, false, true),
+                   ["rustc-cdylib-link-arg=-Wl,--whole-archive", "rustc-cdylib-link-arg=out/checksums.lib", "rustc-cdylib-link-arg=-Wl,--no-whole-archive"]);
+    }
+
+    #[test]
+    fn crate_type_default() {
+        assert_eq!(CrateType::default(), CrateType::Bin);
+    }
+
+    #[test]
+    fn resource_with_utf8_bom() {
+        use super::{Options, resource_to_compile};
+
+        let dir = test_dir("utf8-bom");
+        let resource = dir.join("app.rc");
+        fs::write(&resource, b"\xEF\xBB\xBF1 RCDATA { \"\xC3\xA9\" }\n").unwrap();
+
+        let mut options = Options::new();
+        assert_eq!(resource_to_compile(&resource, &dir, &mut options).unwrap(), (resource.clone(), Some(65001)));
+        assert_eq!(options.codepage, Some(65001));
+
+        let mut options = Options::new().codepage(1252);
+        assert_eq!(resource_to_compile(&resource, &dir, &mut options).unwrap(), (resource.clone(), None));
+        assert_eq!(options.codepage, Some(1252));
+
+        fs::write(&resource, b"1 RCDATA { \"e\" }\n").unwrap();
+        assert_eq!(resource_to_compile(&resource, &dir, &mut Options::new()).unwrap(), (resource, None));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn probe_writable_dir() {
+        let dir = test_dir("writable");
+        probe_writable(&dir).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(probe_writable(&dir).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn probe_read_only_dir() {
+        use super::{CompileError, Options, compile_explicit};
+        use std::os::unix::fs::PermissionsExt;
+        use std::path::Path;
+
+        let dir = test_dir("read-only");
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Privileged users, like root in containers, can write to it regardless, so there's nothing to test
+        let privileged = fs::File::create(dir.join("privileged")).is_ok();
+        let ret = if privileged {
+            None
+        } else {
+            Some((probe_writable(&dir), compile_explicit(Path::new("checksums.rc"), &dir, "x86_64-pc-windows-gnu", Options::new())))
+        };
+
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        match ret {
+            None => eprintln!("probe_read_only_dir skipped: running privileged, so the directory is writable regardless of its permissions"),
+            Some((probed, compiled)) => {
+                assert_eq!(probed.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+                match compiled {
+                    Err(CompileError::OutputDirNotWritable(ref out_dir, _)) => assert_eq!(out_dir, &dir),
+                    ret => panic!("{:?}", ret),
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn compiler_under_spaced_sdk_root() {
+        use super::{OutputMode, Options, run_compiler_once};
+        use std::os::unix::fs::PermissionsExt;
+        use std::ffi::OsString;
+
+        let root = test_dir("spaced-sdk-root");
+        let bin = root.join("Program Files (x86)").join("Windows Kits").join("10").join("bin").join("10.0.22621.0").join("x64");
+        fs::create_dir_all(&bin).unwrap();
+        let rc = bin.join("rc");
+        fs::write(&rc, "#!/bin/sh\nprintf '%s\\n' \"$@\"\n").unwrap();
+        fs::set_permissions(&rc, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let resource = bin.join("My Resources").join("app.rc");
+        let arguments = [OsString::from("/fo"), OsString::from("C:\\Program Files (x86)\\out.res"), resource.clone().into_os_string()];
+        let (stdout, _) = run_compiler_once(&rc, &arguments, &Options::new().output(OutputMode::Captured)).map_err(|(e, _)| e).unwrap();
+        assert_eq!(stdout, format!("/fo\nC:\\Program Files (x86)\\out.res\n{}\n", resource.display()));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn alternative_compiler_after_failure() {
+        use super::{CompileError, OutputMode, Options, ResourceCompiler, run_compiler_or_alternatives};
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("alternative-compiler");
+        let stub = |name: &str, script: &str| {
+            let path = dir.join(name);
+            fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+        let windres = stub("windres", "exit 1");
+        let llvm_windres = stub("llvm-windres", "echo compiled");
+        let missing = dir.join("missing-windres");
+        let options = Options::new().output(OutputMode::Captured);
+
+        match run_compiler_or_alternatives(&windres, vec![llvm_windres.clone()], &[], &options) {
+            Err(CompileError::CompilerFailed(ref compiler, _)) => assert_eq!(compiler, &windres),
+            ret => panic!("{:?}", ret),
+        }
+
+        let options = options.try_alternative_compilers(true);
+        let (output, alternative) = run_compiler_or_alternatives(&windres, vec![missing.clone(), llvm_windres.clone()], &[], &options).unwrap();
+        assert_eq!(output.0, "compiled\n");
+        assert_eq!(alternative.as_ref(), Some(&llvm_windres));
+        match run_compiler_or_alternatives(&windres, vec![windres.clone(), missing.clone()], &[], &options) {
+            Err(CompileError::AllCompilersFailed(ref errors)) => assert_eq!(errors.len(), 2),
+            ret => panic!("{:?}", ret),
+        }
+
+        // The follow-up steps go with the alternative, not the compiler that failed
+        let comp = ResourceCompiler::new("x86_64-pc-windows-gnu");
+        assert_eq!(comp.thin_archive(&llvm_windres, "app.o", "libapp.a").unwrap().0, dir.join("llvm-ar"));
+        assert_eq!(comp.res_to_output(&llvm_windres, "app.res", "app.o", &options).unwrap().0, llvm_windres);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
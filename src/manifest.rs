@@ -0,0 +1,732 @@
+use std::error::Error;
+use std::fmt;
+
+
+/// A Windows OS version an application can declare support for in its manifest's `<compatibility>` section.
+///
+/// Declaring these changes what `GetVersionEx()` & co. return, as well as a few other OS behaviours
+/// (see [MSDN](https://docs.microsoft.com/en-us/windows/win32/sysinfo/targeting-your-application-at-windows-8-1)).
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SupportedOs {
+    WindowsVista,
+    Windows7,
+    Windows8,
+    Windows81,
+    Windows10,
+    /// Windows 11 shares its `supportedOS` GUID with Windows 10
+    Windows11,
+}
+
+impl SupportedOs {
+    /// The `supportedOS` `Id` GUID for this OS version, braces included.
+    pub fn id(self) -> &'static str {
+        match self {
+            SupportedOs::WindowsVista => "{e2011457-1546-43c5-a5fe-008deee3d3f0}",
+            SupportedOs::Windows7 => "{35138b9a-5d96-4fbd-8e2d-a2440225f93a}",
+            SupportedOs::Windows8 => "{4a2f28e3-53b9-4441-ba9c-d69d4a4a6e38}",
+            SupportedOs::Windows81 => "{1f676c76-80e1-4239-95bb-83d0f6d0da78}",
+            SupportedOs::Windows10 | SupportedOs::Windows11 => "{8e0f7a12-bfb3-4fe8-b9a5-48fd50a15a9a}",
+        }
+    }
+}
+
+
+/// How an application handles display scaling, as declared by its manifest's `<dpiAware>`/`<dpiAwareness>` elements.
+///
+/// See [MSDN](https://docs.microsoft.com/en-us/windows/win32/hidpi/setting-the-default-dpi-awareness-for-a-process).
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DpiAwareness {
+    /// The application is bitmap-stretched by the system on high-DPI displays, which is the default
+    Unaware,
+    /// The application scales itself to the primary display's DPI at startup
+    System,
+    /// The application scales itself to each display's DPI as windows move between them (Windows 8.1+)
+    PerMonitor,
+    /// As `PerMonitor`, but with the system also scaling non-client areas, dialogs, and common controls (Windows 10 1703+),
+    /// falling back to `PerMonitor` on older systems
+    PerMonitorV2,
+}
+
+impl DpiAwareness {
+    /// The legacy `<dpiAware>` value, understood by all versions
+    fn dpi_aware(self) -> &'static str {
+        match self {
+            DpiAwareness::Unaware => "false",
+            DpiAwareness::System => "true",
+            DpiAwareness::PerMonitor | DpiAwareness::PerMonitorV2 => "true/pm",
+        }
+    }
+
+    /// The Windows 10 1607+ `<dpiAwareness>` value, which overrides `<dpiAware>`, if needed
+    fn dpi_awareness(self) -> Option<&'static str> {
+        match self {
+            DpiAwareness::PerMonitorV2 => Some("PerMonitorV2, PerMonitor"),
+            _ => None,
+        }
+    }
+}
+
+
+/// The kind of application a manifest is for, to tailor its defaults with [`Manifest::for_subsystem()`](struct.Manifest.html#method.for_subsystem),
+/// matching the `#![windows_subsystem]` attribute.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Subsystem {
+    /// A console application, which is what's built by default
+    Console,
+    /// A GUI application, with `#![windows_subsystem = "windows"]`
+    Gui,
+}
+
+
+/// The privileges an application requests to run with, declared by its manifest's `<requestedExecutionLevel>` element.
+///
+/// See [MSDN](https://docs.microsoft.com/en-us/windows/security/identity-protection/user-account-control/how-user-account-control-works).
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ExecutionLevel {
+    /// The application runs with the privileges of the process starting it, which is the default
+    AsInvoker,
+    /// The application runs with the highest privileges available to the user, prompting administrators for elevation
+    HighestAvailable,
+    /// The application requires administrator privileges, prompting for elevation, or for credentials for other users
+    RequireAdministrator,
+}
+
+impl ExecutionLevel {
+    /// The `requestedExecutionLevel` `level` value
+    fn level(self) -> &'static str {
+        match self {
+            ExecutionLevel::AsInvoker => "asInvoker",
+            ExecutionLevel::HighestAvailable => "highestAvailable",
+            ExecutionLevel::RequireAdministrator => "requireAdministrator",
+        }
+    }
+}
+
+
+/// The identity of a side-by-side assembly an application depends on, declared with
+/// [`Manifest::dependency()`](struct.Manifest.html#method.dependency).
+///
+/// See [MSDN](https://docs.microsoft.com/en-us/windows/win32/sbscs/assembly-manifests) for the attributes' meaning.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AssemblyIdentity {
+    name: String,
+    version: String,
+    processor_architecture: Option<String>,
+    public_key_token: Option<String>,
+    language: Option<String>,
+}
+
+impl AssemblyIdentity {
+    /// The assembly with the specified name and four-part version, like `"6.0.0.0"`.
+    ///
+    /// The version is validated when rendering.
+    pub fn new<N: Into<String>, V: Into<String>>(name: N, version: V) -> AssemblyIdentity {
+        AssemblyIdentity {
+            name: name.into(),
+            version: version.into(),
+            processor_architecture: None,
+            public_key_token: None,
+            language: None,
+        }
+    }
+
+    /// Version 6 of the Common Controls, needed for themed controls and some newer ones, like task dialogs.
+    pub fn common_controls_v6() -> AssemblyIdentity {
+        AssemblyIdentity::new("Microsoft.Windows.Common-Controls", "6.0.0.0")
+            .processor_architecture("*")
+            .public_key_token("6595b64144ccf1df")
+            .language("*")
+    }
+
+    /// Set the `processorArchitecture`, like `"amd64"`, or `"*"` for the one matching the application.
+    pub fn processor_architecture<A: Into<String>>(mut self, arch: A) -> AssemblyIdentity {
+        self.processor_architecture = Some(arch.into());
+        self
+    }
+
+    /// Set the `publicKeyToken` of a signed assembly, as 16 hexadecimal digits.
+    ///
+    /// The token is validated when rendering.
+    pub fn public_key_token<T: Into<String>>(mut self, token: T) -> AssemblyIdentity {
+        self.public_key_token = Some(token.into());
+        self
+    }
+
+    /// Set the `language`, like `"en-us"`, or `"*"` for the one matching the user.
+    pub fn language<L: Into<String>>(mut self, language: L) -> AssemblyIdentity {
+        self.language = Some(language.into());
+        self
+    }
+
+    fn validate(&self) -> Result<(), ManifestError> {
+        if self.version.split('.').count() != 4 || self.version.split('.').any(|p| p.parse::<u16>().is_err()) {
+            return Err(ManifestError::MalformedAssemblyVersion(self.version.clone()));
+        }
+        if let Some(ref token) = self.public_key_token {
+            if token.len() != 16 || !token.bytes().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ManifestError::MalformedPublicKeyToken(token.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    fn to_xml(&self) -> String {
+        let mut xml = format!("<assemblyIdentity type=\"win32\" name=\"{}\" version=\"{}\"", xml_escape(&self.name), self.version);
+        if let Some(ref arch) = self.processor_architecture {
+            xml.push_str(&format!(" processorArchitecture=\"{}\"", xml_escape(arch)));
+        }
+        if let Some(ref token) = self.public_key_token {
+            xml.push_str(&format!(" publicKeyToken=\"{}\"", token));
+        }
+        if let Some(ref language) = self.language {
+            xml.push_str(&format!(" language=\"{}\"", xml_escape(language)));
+        }
+        xml.push_str("/>");
+        xml
+    }
+}
+
+
+/// An error rendering a [`Manifest`](struct.Manifest.html).
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ManifestError {
+    /// The specified `supportedOS` `Id` isn't a braced GUID
+    MalformedSupportedOsId(String),
+    /// Different DPI awareness levels were requested
+    ConflictingDpiAwareness(Vec<DpiAwareness>),
+    /// A dependency's version isn't four dot-separated 16-bit numbers
+    MalformedAssemblyVersion(String),
+    /// A dependency's `publicKeyToken` isn't 16 hexadecimal digits
+    MalformedPublicKeyToken(String),
+    /// A fragment's `<assembly>` element isn't closed, or it contains another one
+    MalformedFragment(String),
+    /// Fragments bind the same namespace prefix to different namespaces
+    ConflictingNamespacePrefix(String),
+    /// Different execution levels were requested
+    ConflictingExecutionLevel(Vec<ExecutionLevel>),
+    /// The requested execution level and fragments, or several fragments, have `<trustInfo>` elements
+    DuplicateTrustInfo,
+    /// The [UTF-8 code page](struct.Manifest.html#method.utf8_code_page) was requested, and a fragment sets the specified one
+    ConflictingActiveCodePage(String),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ManifestError::MalformedSupportedOsId(ref id) => {
+                write!(f, "supportedOS Id \"{}\" is not a GUID of the form {{xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx}}", id)
+            }
+            ManifestError::ConflictingDpiAwareness(ref levels) => write!(f, "conflicting DPI awareness levels {:?}", levels),
+            ManifestError::MalformedAssemblyVersion(ref version) => write!(f, "assembly version \"{}\" is not of the form a.b.c.d", version),
+            ManifestError::MalformedPublicKeyToken(ref token) => write!(f, "publicKeyToken \"{}\" is not 16 hexadecimal digits", token),
+            ManifestError::MalformedFragment(ref start) => write!(f, "manifest fragment starting with \"{}\" has an unclosed or nested <assembly>", start),
+            ManifestError::ConflictingNamespacePrefix(ref prefix) => write!(f, "manifest fragments bind namespace prefix \"{}\" differently", prefix),
+            ManifestError::ConflictingExecutionLevel(ref levels) => write!(f, "conflicting execution levels {:?}", levels),
+            ManifestError::DuplicateTrustInfo => f.write_str("multiple <trustInfo> elements in the execution level and manifest fragments"),
+            ManifestError::ConflictingActiveCodePage(ref codepage) => {
+                write!(f, "UTF-8 code page requested, but a manifest fragment sets <activeCodePage>{}</activeCodePage>", codepage)
+            }
+        }
+    }
+}
+
+impl Error for ManifestError {}
+
+
+/// An application manifest, built up from the parts you care about and rendered to XML for embedding.
+///
+/// # Examples
+///
+/// ```rust
+/// # use embed_resource::{Manifest, SupportedOs};
+/// let xml = Manifest::new()
+///     .supported_os(SupportedOs::Windows7)
+///     .supported_os(SupportedOs::Windows10)
+///     .to_xml()
+///     .unwrap();
+/// assert!(xml.contains(r#"<supportedOS Id="{35138b9a-5d96-4fbd-8e2d-a2440225f93a}"/>"#));
+/// ```
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct Manifest {
+    supported_os: Vec<String>,
+    dpi_awareness: Vec<DpiAwareness>,
+    dependencies: Vec<AssemblyIdentity>,
+    execution_levels: Vec<ExecutionLevel>,
+    utf8_code_page: bool,
+    fragments: Vec<String>,
+}
+
+impl Manifest {
+    /// An empty manifest, equivalent to no manifest at all.
+    pub fn new() -> Manifest {
+        Manifest::default()
+    }
+
+    /// A manifest with the defaults appropriate for the specified kind of application:
+    ///   * for [`Subsystem::Gui`](enum.Subsystem.html#variant.Gui), [`DpiAwareness::PerMonitorV2`](enum.DpiAwareness.html#variant.PerMonitorV2),
+    ///     since the system's bitmap-stretching blurs text, and a dependency on [version 6 of the Common Controls](struct.AssemblyIdentity.html#method.common_controls_v6),
+    ///     since the default version 5 is unthemed;
+    ///   * for [`Subsystem::Console`](enum.Subsystem.html#variant.Console), the [UTF-8 code page](#method.utf8_code_page),
+    ///     since console output usually goes through the C runtime's narrow strings, which otherwise mangle non-ANSI text.
+    ///
+    /// Further settings can be added as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use embed_resource::{Manifest, Subsystem};
+    /// let xml = Manifest::for_subsystem(Subsystem::Gui).to_xml().unwrap();
+    /// assert!(xml.contains("PerMonitorV2, PerMonitor</dpiAwareness>"));
+    /// assert!(xml.contains(r#"name="Microsoft.Windows.Common-Controls""#));
+    ///
+    /// let xml = Manifest::for_subsystem(Subsystem::Console).to_xml().unwrap();
+    /// assert!(xml.contains("UTF-8</activeCodePage>"));
+    /// assert!(!xml.contains("dpiAware"));
+    /// ```
+    pub fn for_subsystem(subsystem: Subsystem) -> Manifest {
+        match subsystem {
+            Subsystem::Console => Manifest::new().utf8_code_page(true),
+            Subsystem::Gui => Manifest::new().dpi_awareness(DpiAwareness::PerMonitorV2).dependency(AssemblyIdentity::common_controls_v6()),
+        }
+    }
+
+    /// Declare support for the specified OS version.
+    pub fn supported_os(self, os: SupportedOs) -> Manifest {
+        self.supported_os_id(os.id())
+    }
+
+    /// Declare support for the OS version with the specified `supportedOS` `Id`,
+    /// for versions newer than this library.
+    ///
+    /// The `Id` is validated to be a braced GUID when rendering.
+    pub fn supported_os_id<I: Into<String>>(mut self, id: I) -> Manifest {
+        let id = id.into();
+        if !self.supported_os.iter().any(|i| i.eq_ignore_ascii_case(&id)) {
+            self.supported_os.push(id);
+        }
+        self
+    }
+
+    /// Declare the specified DPI awareness level.
+    ///
+    /// `PerMonitorV2` emits both the Windows 10 `<dpiAwareness>` element and the legacy `<dpiAware>` element,
+    /// so that the application is still per-monitor-aware on Windows 8.1 and early Windows 10.
+    ///
+    /// Declaring different levels is an error when rendering.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use embed_resource::{DpiAwareness, Manifest};
+    /// let xml = Manifest::new().dpi_awareness(DpiAwareness::PerMonitorV2).to_xml().unwrap();
+    /// assert!(xml.contains("true/pm</dpiAware>"));
+    /// assert!(xml.contains("PerMonitorV2, PerMonitor</dpiAwareness>"));
+    ///
+    /// assert!(Manifest::new().dpi_awareness(DpiAwareness::System).dpi_awareness(DpiAwareness::PerMonitor).to_xml().is_err());
+    /// ```
+    pub fn dpi_awareness(mut self, level: DpiAwareness) -> Manifest {
+        if !self.dpi_awareness.contains(&level) {
+            self.dpi_awareness.push(level);
+        }
+        self
+    }
+
+    /// Declare a dependency on the specified side-by-side assembly.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use embed_resource::{AssemblyIdentity, Manifest};
+    /// let xml = Manifest::new().dependency(AssemblyIdentity::common_controls_v6()).to_xml().unwrap();
+    /// assert!(xml.contains(r#"<assemblyIdentity type="win32" name="Microsoft.Windows.Common-Controls" version="6.0.0.0" processorArchitecture="*" publicKeyToken="6595b64144ccf1df" language="*"/>"#));
+    ///
+    /// assert!(Manifest::new().dependency(AssemblyIdentity::new("Contoso.Widgets", "1.0")).to_xml().is_err());
+    /// ```
+    pub fn dependency(mut self, assembly: AssemblyIdentity) -> Manifest {
+        if !self.dependencies.contains(&assembly) {
+            self.dependencies.push(assembly);
+        }
+        self
+    }
+
+    /// Make UTF-8 the process' ANSI and OEM code page, used by the `*A()` functions and the C runtime (Windows 10 1903+),
+    /// with an `<activeCodePage>` element.
+    ///
+    /// Older versions ignore the element, so this also declares [support](#method.supported_os) for Windows 10,
+    /// and that it was tested with 1903 (`<maxversiontested Id="10.0.18362.0"/>`).
+    /// Including [fragments](#method.fragment) with their own `<activeCodePage>` is an error when rendering.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use embed_resource::{Manifest, SupportedOs};
+    /// let xml = Manifest::new().utf8_code_page(true).to_xml().unwrap();
+    /// assert!(xml.contains("UTF-8</activeCodePage>"));
+    /// assert!(xml.contains(&format!(r#"<supportedOS Id="{}"/>"#, SupportedOs::Windows10.id())));
+    /// assert!(xml.contains(r#"<maxversiontested Id="10.0.18362.0"/>"#));
+    ///
+    /// assert!(Manifest::new()
+    ///     .utf8_code_page(true)
+    ///     .fragment(r#"<application xmlns="urn:schemas-microsoft-com:asm.v3"><windowsSettings>
+    ///   <activeCodePage xmlns="http://schemas.microsoft.com/SMI/2019/WindowsSettings">Legacy</activeCodePage>
+    /// </windowsSettings></application>"#)
+    ///     .to_xml()
+    ///     .is_err());
+    /// ```
+    pub fn utf8_code_page(mut self, utf8: bool) -> Manifest {
+        self.utf8_code_page = utf8;
+        self
+    }
+
+    /// Request the specified execution level, like administrator privileges, in a `<trustInfo>` element.
+    ///
+    /// Requesting different levels, or a level and including [fragments](#method.fragment) with their own `<trustInfo>`,
+    /// is an error when rendering.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use embed_resource::{ExecutionLevel, Manifest};
+    /// let xml = Manifest::new().execution_level(ExecutionLevel::RequireAdministrator).to_xml().unwrap();
+    /// assert!(xml.contains(r#"<requestedExecutionLevel level="requireAdministrator" uiAccess="false"/>"#));
+    ///
+    /// assert!(Manifest::new()
+    ///     .execution_level(ExecutionLevel::AsInvoker)
+    ///     .fragment(r#"<asmv3:trustInfo xmlns:asmv3="urn:schemas-microsoft-com:asm.v3"/>"#)
+    ///     .to_xml()
+    ///     .is_err());
+    /// ```
+    pub fn execution_level(mut self, level: ExecutionLevel) -> Manifest {
+        if !self.execution_levels.contains(&level) {
+            self.execution_levels.push(level);
+        }
+        self
+    }
+
+    /// Include the specified XML, like a manifest file maintained separately, after the generated parts.
+    ///
+    /// The fragment can be a whole manifest, whose XML declaration and `<assembly>` element are dropped,
+    /// keeping only the latter's contents and namespace prefix declarations, or just the elements to go into `<assembly>`.
+    /// Fragments aren't otherwise checked, so two specifying the same setting conflict.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use embed_resource::{DpiAwareness, Manifest};
+    /// let xml = Manifest::new()
+    ///     .dpi_awareness(DpiAwareness::System)
+    ///     .fragment(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+    /// <assembly xmlns="urn:schemas-microsoft-com:asm.v1" xmlns:asmv3="urn:schemas-microsoft-com:asm.v3" manifestVersion="1.0">
+    ///   <asmv3:trustInfo>
+    ///     <asmv3:security><asmv3:requestedPrivileges><asmv3:requestedExecutionLevel level="asInvoker"/></asmv3:requestedPrivileges></asmv3:security>
+    ///   </asmv3:trustInfo>
+    /// </assembly>"#)
+    ///     .fragment(r#"<description>An application</description>"#)
+    ///     .to_xml()
+    ///     .unwrap();
+    /// assert_eq!(xml.matches("<assembly").count(), 1);
+    /// assert!(xml.contains(r#"xmlns:asmv3="urn:schemas-microsoft-com:asm.v3""#));
+    /// assert!(xml.contains("<asmv3:trustInfo>"));
+    /// assert!(xml.contains("<description>An application</description>"));
+    ///
+    /// assert!(Manifest::new().fragment("<assembly><assembly/></assembly>").to_xml().is_err());
+    /// assert!(Manifest::new().fragment(r#"<assembly xmlns:a="urn:a">"#).to_xml().is_err());
+    /// ```
+    pub fn fragment<X: Into<String>>(mut self, xml: X) -> Manifest {
+        self.fragments.push(xml.into());
+        self
+    }
+
+    /// Render the manifest to its XML form, validating all parts.
+    pub fn to_xml(&self) -> Result<String, ManifestError> {
+        if let Some(id) = self.supported_os.iter().find(|id| !is_braced_guid(id)) {
+            return Err(ManifestError::MalformedSupportedOsId(id.clone()));
+        }
+        if self.dpi_awareness.len() > 1 {
+            return Err(ManifestError::ConflictingDpiAwareness(self.dpi_awareness.clone()));
+        }
+        for dependency in &self.dependencies {
+            dependency.validate()?;
+        }
+        let mut prefixes: Vec<NamespaceDeclaration> = vec![];
+        let mut contents = vec![];
+        for fragment in &self.fragments {
+            let (declarations, content) = split_fragment(fragment)?;
+            for (prefix, namespace) in declarations {
+                match prefixes.iter().position(|(p, _)| *p == prefix) {
+                    Some(i) if prefixes[i].1 != namespace => return Err(ManifestError::ConflictingNamespacePrefix(prefix)),
+                    Some(_) => {}
+                    None => prefixes.push((prefix, namespace)),
+                }
+            }
+            contents.push(content);
+        }
+        if self.execution_levels.len() > 1 {
+            return Err(ManifestError::ConflictingExecutionLevel(self.execution_levels.clone()));
+        }
+        if self.execution_levels.len() + contents.iter().map(|c| trust_info_count(c)).sum::<usize>() > 1 {
+            return Err(ManifestError::DuplicateTrustInfo);
+        }
+        if self.utf8_code_page {
+            if let Some(codepage) = contents.iter().filter_map(|c| active_code_page(c)).next() {
+                return Err(ManifestError::ConflictingActiveCodePage(codepage.to_string()));
+            }
+        }
+        let mut supported_os = self.supported_os.clone();
+        if self.utf8_code_page && !supported_os.iter().any(|id| id.eq_ignore_ascii_case(SupportedOs::Windows10.id())) {
+            supported_os.push(SupportedOs::Windows10.id().to_string());
+        }
+
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+        xml.push_str("<assembly xmlns=\"urn:schemas-microsoft-com:asm.v1\"");
+        for (prefix, namespace) in &prefixes {
+            xml.push_str(&format!(" xmlns:{}=\"{}\"", prefix, namespace));
+        }
+        xml.push_str(" manifestVersion=\"1.0\">\n");
+
+        for dependency in &self.dependencies {
+            xml.push_str("  <dependency>\n");
+            xml.push_str("    <dependentAssembly>\n");
+            xml.push_str(&format!("      {}\n", dependency.to_xml()));
+            xml.push_str("    </dependentAssembly>\n");
+            xml.push_str("  </dependency>\n");
+        }
+
+        if !supported_os.is_empty() {
+            xml.push_str("  <compatibility xmlns=\"urn:schemas-microsoft-com:compatibility.v1\">\n");
+            xml.push_str("    <application>\n");
+            for id in &supported_os {
+                xml.push_str(&format!("      <supportedOS Id=\"{}\"/>\n", id));
+            }
+            if self.utf8_code_page {
+                xml.push_str("      <maxversiontested Id=\"10.0.18362.0\"/>\n");
+            }
+            xml.push_str("    </application>\n");
+            xml.push_str("  </compatibility>\n");
+        }
+
+        if !self.dpi_awareness.is_empty() || self.utf8_code_page {
+            xml.push_str("  <application xmlns=\"urn:schemas-microsoft-com:asm.v3\">\n");
+            xml.push_str("    <windowsSettings>\n");
+            if let Some(&level) = self.dpi_awareness.first() {
+                xml.push_str(&format!("      <dpiAware xmlns=\"http://schemas.microsoft.com/SMI/2005/WindowsSettings\">{}</dpiAware>\n",
+                                      level.dpi_aware()));
+                if let Some(awareness) = level.dpi_awareness() {
+                    xml.push_str(&format!("      <dpiAwareness xmlns=\"http://schemas.microsoft.com/SMI/2016/WindowsSettings\">{}</dpiAwareness>\n",
+                                          awareness));
+                }
+            }
+            if self.utf8_code_page {
+                xml.push_str("      <activeCodePage xmlns=\"http://schemas.microsoft.com/SMI/2019/WindowsSettings\">UTF-8</activeCodePage>\n");
+            }
+            xml.push_str("    </windowsSettings>\n");
+            xml.push_str("  </application>\n");
+        }
+
+        if let Some(&level) = self.execution_levels.first() {
+            xml.push_str("  <trustInfo xmlns=\"urn:schemas-microsoft-com:asm.v3\">\n");
+            xml.push_str("    <security>\n");
+            xml.push_str("      <requestedPrivileges>\n");
+            xml.push_str(&format!("        <requestedExecutionLevel level=\"{}\" uiAccess=\"false\"/>\n", level.level()));
+            xml.push_str("      </requestedPrivileges>\n");
+            xml.push_str("    </security>\n");
+            xml.push_str("  </trustInfo>\n");
+        }
+
+        for content in contents {
+            xml.push_str("  ");
+            xml.push_str(content);
+            xml.push('\n');
+        }
+
+        xml.push_str("</assembly>\n");
+        Ok(xml)
+    }
+}
+
+
+/// `{xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx}`
+fn is_braced_guid(id: &str) -> bool {
+    let id = id.as_bytes();
+    id.len() == 38 && id[0] == b'{' && id[37] == b'}' &&
+    id[1..37].iter().enumerate().all(|(i, &c)| match i {
+        8 | 13 | 18 | 23 => c == b'-',
+        _ => c.is_ascii_hexdigit(),
+    })
+}
+
+/// A namespace prefix and the namespace it's bound to
+type NamespaceDeclaration = (String, String);
+
+/// The namespace prefix declarations (`xmlns:prefix="namespace"`) of the fragment's `<assembly>` element, if any,
+/// and its contents, or the whole fragment if it doesn't have one
+fn split_fragment(fragment: &str) -> Result<(Vec<NamespaceDeclaration>, &str), ManifestError> {
+    let mut rest = fragment.trim();
+    if rest.starts_with("<?xml") {
+        rest = rest.find("?>").map(|end| rest[end + 2..].trim_start()).unwrap_or("");
+    }
+    let malformed = || ManifestError::MalformedFragment(fragment.trim().lines().next().unwrap_or("").to_string());
+
+    let (declarations, content) = if assembly_starts(rest).any(|i| i == 0) {
+        // Attribute values can't contain a raw '>'
+        let tag_end = rest.find('>').ok_or_else(malformed)?;
+        let attributes = &rest["<assembly".len()..tag_end];
+        let declarations = parse_attributes(attributes.trim_end_matches('/'))
+            .into_iter()
+            .filter(|(name, _)| name.starts_with("xmlns:"))
+            .map(|(name, value)| (name["xmlns:".len()..].to_string(), value))
+            .collect();
+
+        let content = if attributes.ends_with('/') {
+            ""
+        } else {
+            let body = rest[tag_end + 1..].trim_end();
+            if !body.ends_with("</assembly>") {
+                return Err(malformed());
+            }
+            body[..body.len() - "</assembly>".len()].trim()
+        };
+        (declarations, content)
+    } else {
+        (vec![], rest)
+    };
+
+    if assembly_starts(content).next().is_some() {
+        return Err(malformed());
+    }
+    Ok((declarations, content))
+}
+
+/// The indices of `<assembly>` start tags
+fn assembly_starts<'s>(s: &'s str) -> impl Iterator<Item = usize> + 's {
+    s.match_indices("<assembly")
+        .map(|(i, _)| i)
+        .filter(move |&i| s[i + "<assembly".len()..].chars().next().map(|c| c.is_whitespace() || c == '>' || c == '/').unwrap_or(true))
+}
+
+/// The number of `<trustInfo>` start tags, with any namespace prefix
+fn trust_info_count(s: &str) -> usize {
+    s.split('<')
+        .skip(1)
+        .filter(|tag| tag.split(|c: char| c.is_whitespace() || c == '/' || c == '>').next().map(|name| name.rsplit(':').next() == Some("trustInfo")).unwrap_or(false))
+        .count()
+}
+
+/// The contents of the first `<activeCodePage>` element, with any namespace prefix
+fn active_code_page(s: &str) -> Option<&str> {
+    s.split('<')
+        .skip(1)
+        .find(|tag| tag.split(|c: char| c.is_whitespace() || c == '/' || c == '>').next().map(|name| name.rsplit(':').next() == Some("activeCodePage")).unwrap_or(false))
+        .map(|tag| match tag.find('>') {
+            Some(end) if !tag[..end].ends_with('/') => tag[end + 1..].trim(),
+            _ => "",
+        })
+}
+
+/// `name="value"` or `name='value'` pairs
+fn parse_attributes(mut s: &str) -> Vec<(String, String)> {
+    let mut ret = vec![];
+    loop {
+        s = s.trim_start();
+        let eq = match s.find('=') {
+            Some(eq) => eq,
+            None => return ret,
+        };
+        let name = s[..eq].trim().to_string();
+        s = s[eq + 1..].trim_start();
+        let quote = match s.chars().next() {
+            Some(q) if q == '"' || q == '\'' => q,
+            _ => return ret,
+        };
+        let end = match s[1..].find(quote) {
+            Some(end) => end + 1,
+            None => return ret,
+        };
+        ret.push((name, s[1..end].to_string()));
+        s = &s[end + 1..];
+    }
+}
+
+/// Check that the document has a single root element, and that its tags are balanced and quoted properly,
+/// returning what's wrong otherwise; entities, names, and encodings aren't checked
+pub(crate) fn check_well_formed(xml: &str) -> Result<(), String> {
+    let mut open: Vec<&str> = vec![];
+    let mut roots = 0;
+    let mut rest = xml.trim_start_matches('\u{feff}');
+    while let Some(lt) = rest.find('<') {
+        if open.is_empty() && !rest[..lt].trim().is_empty() {
+            return Err("text outside of the root element".to_string());
+        }
+        rest = &rest[lt..];
+
+        let (terminator, element) = if rest.starts_with("<!--") {
+            ("-->", false)
+        } else if rest.starts_with("<![CDATA[") {
+            ("]]>", false)
+        } else if rest.starts_with("<?") {
+            ("?>", false)
+        } else {
+            (">", !rest.starts_with("<!"))
+        };
+        let end = if element {
+            tag_end(rest).ok_or_else(|| format!("unterminated tag {}", rest.lines().next().unwrap_or("")))?
+        } else {
+            rest.find(terminator).ok_or_else(|| format!("unterminated {}", rest.lines().next().unwrap_or("")))? + terminator.len()
+        };
+        let tag = &rest[..end];
+        rest = &rest[end..];
+        if !element {
+            continue;
+        }
+
+        let name = tag.trim_start_matches("</").trim_start_matches('<').split(|c: char| c.is_whitespace() || c == '/' || c == '>').next().unwrap_or("");
+        if name.is_empty() {
+            return Err(format!("nameless tag {}", tag));
+        }
+        if tag.starts_with("</") {
+            match open.pop() {
+                Some(o) if o == name => {}
+                Some(o) => return Err(format!("</{}> closes <{}>", name, o)),
+                None => return Err(format!("</{}> closes nothing", name)),
+            }
+        } else {
+            if open.is_empty() {
+                roots += 1;
+                if roots > 1 {
+                    return Err("multiple root elements".to_string());
+                }
+            }
+            if !tag.ends_with("/>") {
+                open.push(name);
+            }
+        }
+    }
+
+    if open.is_empty() && !rest.trim().is_empty() {
+        return Err("text outside of the root element".to_string());
+    }
+    match open.pop() {
+        Some(o) => Err(format!("unclosed <{}>", o)),
+        None if roots == 0 => Err("no root element".to_string()),
+        None => Ok(()),
+    }
+}
+
+/// The index just past the '>' ending the tag at the start, skipping over quoted attribute values
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
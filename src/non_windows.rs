@@ -1,39 +1,162 @@
-use std::process::Command;
-use std::path::PathBuf;
-use std::env;
+use std::ffi::OsString;
+use std::path::{PathBuf, Path};
+use std::{env, fs};
+use super::toolchain::{windres_arguments, rc_arguments, sort_newest_first};
+use super::{ScriptEncoding, DiscoveryStrategy, Options, Arch, ar_for_windres, detect_arch, probed, windres_for_linker};
 
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ResourceCompiler {
     windres: Option<&'static str>,
+    /// `*-windows-msvc` targets are compiled with `llvm-rc`, against the cross-compilation SDK, if one is set up
+    msvc: Option<Arch>,
 }
 
 
 impl ResourceCompiler {
-    pub fn new() -> ResourceCompiler {
-        ResourceCompiler { windres: get_windres_executable() }
+    pub fn new(target: &str) -> ResourceCompiler {
+        ResourceCompiler {
+            windres: get_windres_executable(target),
+            // Otherwise, as before, nothing is compiled, so checking MSVC targets works without the SDK
+            msvc: Some(target).filter(|t| t.ends_with("-windows-msvc") && cross_sdk_root().is_some()).and_then(detect_arch),
+        }
     }
 
     #[inline]
     pub fn is_supported(&self) -> bool {
-        self.windres.is_some()
+        self.windres.is_some() || self.msvc.is_some()
+    }
+
+    pub fn script_encoding(&self) -> ScriptEncoding {
+        ScriptEncoding::Utf8
+    }
+
+    pub fn compiler(&self, options: &Options) -> (PathBuf, DiscoveryStrategy) {
+        if self.msvc.is_some() {
+            return match options.linker.as_ref().and_then(|linker| llvm_rc_for_linker(linker)) {
+                Some(llvm_rc) => (llvm_rc, DiscoveryStrategy::Linker),
+                None => (PathBuf::from("llvm-rc"), DiscoveryStrategy::Path),
+            };
+        }
+        match options.linker.as_ref().and_then(|linker| windres_for_linker(linker)) {
+            Some(windres) => (windres, DiscoveryStrategy::Linker),
+            None => (PathBuf::from(self.windres.expect("Couldn't find windres for this platform")), DiscoveryStrategy::Path),
+        }
     }
 
-    pub fn compile_resource(&self, out_dir: &str, prefix: &str, resource: &str) {
-        let windres = self.windres.expect("Couldn't find windres for this platform");
+    /// The cross SDK is checked for when [selecting](#method.new) `llvm-rc`, not when it's missing
+    pub fn msvc_compiler(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// `llvm-windres` takes `windres`' switches, to be tried if it fails; `llvm-rc` is the only choice for MSVC targets
+    pub fn alternative_compilers(&self, _: &Options) -> Vec<PathBuf> {
+        if self.msvc.is_some() {
+            vec![]
+        } else {
+            vec![PathBuf::from("llvm-windres")]
+        }
+    }
+
+    /// `.res`es are linkable by `lld-link` as well
+    pub fn output_file(&self, out_dir: &str, prefix: &str) -> String {
+        if self.msvc.is_some() {
+            format!("{}/{}.lib", out_dir, prefix)
+        } else {
+            format!("{}/lib{}.a", out_dir, prefix)
+        }
+    }
 
-        let out_file = format!("{}/lib{}.a", out_dir, prefix);
-        match Command::new(windres).args(&["--input", resource, "--output-format=coff", "--output", &out_file]).status() {
-            Ok(stat) if stat.success() => {}
-            Ok(stat) => panic!("{} failed to compile \"{}\" into \"{}\" with {}", windres, resource, out_file, stat),
-            Err(e) => panic!("Couldn't to execute {} to compile \"{}\" into \"{}\": {}", windres, resource, out_file, e),
+    /// `windres`' COFF output is an object already
+    pub fn object_file(&self, out_dir: &str, prefix: &str) -> String {
+        if self.msvc.is_some() {
+            format!("{}/{}.obj", out_dir, prefix)
+        } else {
+            format!("{}/{}.o", out_dir, prefix)
         }
     }
+
+    /// `llvm-rc` only makes `.res`es, which `llvm-cvtres` converts to objects
+    #[inline(always)]
+    pub fn object_needs_res(&self) -> bool {
+        self.msvc.is_some()
+    }
+
+    /// `ar` from the same binutils as the `windres` that compiled the object, with `T` making a thin archive,
+    /// referencing the object instead of containing it; MSVC targets are linked as they would be with `LINK.EXE`
+    pub fn thin_archive(&self, compiler: &Path, object: &str, archive: &str) -> Option<(PathBuf, Vec<OsString>)> {
+        if self.msvc.is_some() {
+            return None;
+        }
+        Some((ar_for_windres(compiler), vec!["rcsT".into(), archive.into(), object.into()]))
+    }
+
+    /// MinGW brings its own headers; for MSVC targets, the extant component directories of the cross-compilation SDK's headers
+    /// with the specified version, or `None` if they're not there
+    pub fn sdk_include_dirs(&self, version: &str) -> Option<Vec<PathBuf>> {
+        if self.msvc.is_none() {
+            return Some(vec![]);
+        }
+        let version_dir = cross_sdk_root()?.join("Include").join(version);
+        if !version_dir.is_dir() {
+            return None;
+        }
+        Some(["um", "shared", "ucrt", "winrt"].iter().map(|component| version_dir.join(component)).filter(|dir| dir.is_dir()).collect())
+    }
+
+    pub fn missing_sdk_headers(&self, _: &Options) -> Vec<PathBuf> {
+        vec![]
+    }
+
+    pub fn arguments(&self, resource: &str, out_file: &str, options: &Options) -> Vec<OsString> {
+        if self.msvc.is_some() {
+            return self.llvm_rc_arguments(resource, out_file, options);
+        }
+        windres_arguments(resource, "--output-format=coff", out_file, options)
+    }
+
+    pub fn res_arguments(&self, resource: &str, res_file: &str, options: &Options) -> Vec<OsString> {
+        if self.msvc.is_some() {
+            return self.llvm_rc_arguments(resource, res_file, options);
+        }
+        windres_arguments(resource, "--output-format=res", res_file, options)
+    }
+
+    /// The `windres` that compiled the `.res` converts it to a COFF object without recompiling;
+    /// for MSVC targets, the output is a `.res` already, so it's just copied, unless it's to be an object
+    pub fn res_to_output(&self, compiler: &Path, res_file: &str, out_file: &str, options: &Options) -> Option<(PathBuf, Vec<OsString>)> {
+        if let Some(arch) = self.msvc {
+            if !options.object.unwrap_or(false) {
+                return None;
+            }
+            let machine = match arch {
+                Arch::X86 => "X86",
+                Arch::X64 => "X64",
+                Arch::Arm => "ARM",
+                Arch::Arm64 => "ARM64",
+            };
+            return Some((cvtres_for_llvm_rc(compiler),
+                         vec!["/nologo".into(), format!("/machine:{}", machine).into(), format!("/out:{}", out_file).into(), res_file.into()]));
+        }
+        Some((compiler.to_path_buf(),
+              vec!["--input".into(), res_file.into(), "--input-format=res".into(), "--output-format=coff".into(), "--output".into(), out_file.into()]))
+    }
+
+    /// `llvm-rc` takes `RC.EXE`'s switches, and also searches `$INCLUDE`, which msvc-wine-style setups set,
+    /// after the cross-compilation SDK's headers, passed explicitly
+    fn llvm_rc_arguments(&self, resource: &str, out_file: &str, options: &Options) -> Vec<OsString> {
+        let sdk_dirs = match options.sdk_include_version {
+            Some(ref version) => self.sdk_include_dirs(version).unwrap_or_default(),
+            None => cross_sdk_include_dirs(),
+        };
+        // llvm-rc doesn't read UTF-16 scripts, and rejects anything but ASCII in the default codepage
+        rc_arguments(resource, out_file, Some(65001), sdk_dirs, options)
+    }
 }
 
 
-fn get_windres_executable() -> Option<&'static str> {
-    match &env::var("TARGET").ok()?[..] {
+fn get_windres_executable(target: &str) -> Option<&'static str> {
+    match target {
         "x86_64-pc-windows-gnu" => Some("x86_64-w64-mingw32-windres"),
         "i686-pc-windows-gnu" => Some("i686-w64-mingw32-windres"),
         _ => None,
@@ -41,6 +164,75 @@ fn get_windres_executable() -> Option<&'static str> {
 }
 
 
+/// The `llvm-rc` from the same LLVM as the `clang-cl`, `clang`, or `lld-link` linker, like `llvm-rc-17` for `/usr/bin/clang-cl-17`,
+/// if it exists, or in `$PATH` if the linker is just a name
+fn llvm_rc_for_linker(linker: &Path) -> Option<PathBuf> {
+    let name = linker.file_name()?.to_str()?;
+    let name = name.trim_end_matches(".exe");
+    let version = ["clang-cl", "clang", "lld-link", "ld.lld"]
+        .iter()
+        .filter(|tool| name.starts_with(*tool))
+        .map(|tool| &name[tool.len()..])
+        .find(|version| version.is_empty() || version.starts_with('-'))?;
+    let llvm_rc = format!("llvm-rc{}{}", version, env::consts::EXE_SUFFIX);
+
+    match linker.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => {
+            let llvm_rc = dir.join(llvm_rc);
+            let exists = llvm_rc.is_file();
+            probed(DiscoveryStrategy::Linker, &llvm_rc, exists);
+            Some(llvm_rc).filter(|_| exists)
+        }
+        _ => env::var_os("PATH").and_then(|path| env::split_paths(&path).map(|dir| dir.join(&llvm_rc)).find(|l| l.is_file())),
+    }
+}
+
+/// The `llvm-cvtres` next to the `llvm-rc`, with the same version suffix, or in `$PATH` if it's just a name, like the latter
+fn cvtres_for_llvm_rc(llvm_rc: &Path) -> PathBuf {
+    let name = llvm_rc.file_name().and_then(|n| n.to_str()).unwrap_or("llvm-rc");
+    llvm_rc.with_file_name(name.replacen("llvm-rc", "llvm-cvtres", 1))
+}
+
+/// `$EMBED_RESOURCE_WINDOWS_SDK`, the root of the Windows SDK and CRT headers for cross-compiling to MSVC targets
+fn cross_sdk_root() -> Option<PathBuf> {
+    env::var_os("EMBED_RESOURCE_WINDOWS_SDK").filter(|root| !root.is_empty()).map(PathBuf::from)
+}
+
+/// The extant include directories of the cross-compilation SDK, either as splatted by `xwin`, in `sdk/include/` and `crt/include/`,
+/// or in the Windows SDK's own layout, in `Include/<version>/`, for the newest version
+fn cross_sdk_include_dirs() -> Vec<PathBuf> {
+    let root = match cross_sdk_root() {
+        Some(root) => root,
+        None => return vec![],
+    };
+
+    let splat = root.join("sdk").join("include");
+    let sdk_include = if splat.is_dir() {
+        splat
+    } else {
+        let mut versions: Vec<OsString> = match fs::read_dir(root.join("Include")) {
+            Ok(rd) => rd.flatten().filter(|entry| entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false)).map(|entry| entry.file_name()).collect(),
+            Err(_) => vec![],
+        };
+        sort_newest_first(&mut versions);
+        match versions.first() {
+            Some(version) => root.join("Include").join(version),
+            None => return vec![],
+        }
+    };
+    ["um", "shared", "ucrt", "winrt"]
+        .iter()
+        .map(|component| sdk_include.join(component))
+        .chain(Some(root.join("crt").join("include")))
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+
 pub fn find_windows_sdk_tool_impl(_: &str) -> Option<PathBuf> {
     None
 }
+
+pub fn find_sdk_tool_impl(_: &str, _: &str) -> Option<PathBuf> {
+    None
+}
@@ -0,0 +1,1137 @@
+use std::path::{PathBuf, Path};
+use std::time::Duration;
+use std::ffi::OsString;
+use super::{Diagnostic, Severity, ResourceId, Arch, CompileError, env_flag};
+use std::env;
+
+
+/// Environment variables starting with this are turned into preprocessor definitions,
+/// e.g. `EMBED_RESOURCE_DEFINE_BUILD_ID=1234` becomes `BUILD_ID=1234`
+pub const ENV_DEFINE_PREFIX: &str = "EMBED_RESOURCE_DEFINE_";
+
+
+/// The environment variables passed to the resource compiler with [`Options::minimal_environment()`](struct.Options.html#method.minimal_environment)
+pub const ESSENTIAL_ENV_VARS: &[&str] = &["INCLUDE", "PATH", "TEMP", "TMP", "SYSTEMROOT"];
+
+
+/// What to do with the resource compiler's output, as set with [`Options::output()`](struct.Options.html#method.output).
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OutputMode {
+    /// Pass it to this process' standard error stream, where cargo shows it for failed builds or with `-vv`, the default
+    Inherit,
+    /// Return it in [`Artifact::stdout`](struct.Artifact.html#structfield.stdout) and
+    /// [`Artifact::stderr`](struct.Artifact.html#structfield.stderr), or, if compilation fails, pass it along as with `Inherit`
+    Captured,
+    /// Discard it, even if compilation fails
+    Silent,
+}
+
+impl Default for OutputMode {
+    fn default() -> OutputMode {
+        OutputMode::Inherit
+    }
+}
+
+
+/// The kind of image the resources end up in, as set with [`Options::crate_type()`](struct.Options.html#method.crate_type).
+///
+/// Build scripts aren't told which crate types are being built, so this needs to be specified for libraries.
+/// Resources for a `staticlib` are linked into whatever image eventually links it, so use the kind of that image.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CrateType {
+    /// An executable, the default
+    Bin,
+    /// A DLL
+    Cdylib,
+}
+
+impl Default for CrateType {
+    fn default() -> CrateType {
+        CrateType::Bin
+    }
+}
+
+impl CrateType {
+    /// The ID the loader expects the image's manifest to have:
+    /// `CREATEPROCESS_MANIFEST_RESOURCE_ID` (1) for executables and `ISOLATIONAWARE_MANIFEST_RESOURCE_ID` (2) for DLLs,
+    /// which ignore manifests with ID 1.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use embed_resource::CrateType;
+    /// assert_eq!(CrateType::Bin.manifest_resource_id(), 1);
+    /// ```
+    ///
+    /// ```rust
+    /// # use embed_resource::CrateType;
+    /// assert_eq!(CrateType::Cdylib.manifest_resource_id(), 2);
+    /// ```
+    pub fn manifest_resource_id(self) -> u16 {
+        match self {
+            CrateType::Bin => 1,
+            CrateType::Cdylib => 2,
+        }
+    }
+}
+
+
+/// The encoding generated resource scripts are written in, as set with
+/// [`Options::generated_script_encoding()`](struct.Options.html#method.generated_script_encoding).
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ScriptEncoding {
+    /// UTF-8 without a BOM, the default for `windres`, which doesn't understand UTF-16
+    Utf8,
+    /// UTF-16LE with a BOM, the default for `RC.EXE`, since all versions read it reliably
+    Utf16,
+}
+
+
+/// Options controlling how a resource file is compiled.
+///
+/// No include directories are added implicitly, in particular not `$OUT_DIR`,
+/// so `#include "..."`s are looked up in the including file's directory,
+/// then in those added by [`Options::base_dir()`](#method.base_dir), [`Options::include_dir()`](#method.include_dir),
+/// and [`Options::explicit_includes()`](#method.explicit_includes),
+/// then wherever the compiler searches by itself, like `%INCLUDE%` for `RC.EXE`.
+///
+/// Options from several sources can be layered with [`Options::merge()`](#method.merge),
+/// which also describes how the environment is layered under them.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// extern crate embed_resource;
+/// use embed_resource::Options;
+///
+/// fn main() {
+///     embed_resource::compile_with("checksums.rc", Options::new()
+///         .define("BUILD_FLAVOUR", Some("\"nightly\""))
+///         .define("HAS_CHECKSUMS", None)).unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct Options {
+    pub(crate) symbols: Vec<Symbol>,
+    pub(crate) expected_types: Vec<ResourceId>,
+    pub(crate) only_types: Vec<ResourceId>,
+    pub(crate) skipped_types: Vec<ResourceId>,
+    pub(crate) explicit_includes: Option<bool>,
+    pub(crate) warnings_as_errors: Option<bool>,
+    pub(crate) compile_commands: Option<PathBuf>,
+    pub(crate) null_terminate_strings: Option<bool>,
+    pub(crate) crate_type: Option<CrateType>,
+    pub(crate) suppress_directives: Option<bool>,
+    pub(crate) rerun_directives: Option<bool>,
+    pub(crate) script_encoding: Option<ScriptEncoding>,
+    pub(crate) cache: Option<bool>,
+    pub(crate) emit_res: Option<bool>,
+    pub(crate) retry: Option<(u32, Duration)>,
+    pub(crate) minimal_environment: Option<bool>,
+    pub(crate) output: Option<OutputMode>,
+    pub(crate) disable_linker_manifest: Option<bool>,
+    pub(crate) cargo_version_defines: Option<bool>,
+    pub(crate) base_dir: Option<PathBuf>,
+    pub(crate) include_dirs: Vec<PathBuf>,
+    pub(crate) reproducible: Option<bool>,
+    pub(crate) linker: Option<PathBuf>,
+    pub(crate) codepage: Option<u16>,
+    pub(crate) compiler_limits: Option<(Option<usize>, Option<Duration>)>,
+    pub(crate) copy_to: Option<PathBuf>,
+    pub(crate) object: Option<bool>,
+    pub(crate) suppressed_warnings: Vec<String>,
+    pub(crate) force: Option<bool>,
+    pub(crate) export: Option<bool>,
+    pub(crate) language: Option<u16>,
+    pub(crate) embed_source: Option<bool>,
+    pub(crate) sdk_include_version: Option<String>,
+    pub(crate) thin_archive: Option<bool>,
+    pub(crate) list_resources: Option<bool>,
+    pub(crate) arch: Option<Arch>,
+    pub(crate) path_variables: Vec<(String, PathBuf)>,
+    pub(crate) output_path: Option<PathBuf>,
+    pub(crate) verbose: Option<bool>,
+    pub(crate) try_alternatives: Option<bool>,
+    pub(crate) temp_dir: Option<PathBuf>,
+}
+
+impl Options {
+    /// The default options, equivalent to calling [`compile()`](fn.compile.html).
+    pub fn new() -> Options {
+        Options::default()
+    }
+
+    /// Define a preprocessor symbol, optionally with a value, as if by `#define name value`.
+    ///
+    /// Redefining a symbol replaces its previous value.
+    pub fn define<N: Into<String>>(mut self, name: N, value: Option<&str>) -> Options {
+        let name = name.into();
+        let value = value.map(str::to_string);
+        match self.symbols.iter().position(|s| match *s {
+            Symbol::Define(ref n, _) => *n == name,
+            Symbol::Undefine(_) => false,
+        }) {
+            Some(i) => self.symbols[i] = Symbol::Define(name, value),
+            None => self.symbols.push(Symbol::Define(name, value)),
+        }
+        self
+    }
+
+    /// Undefine a preprocessor symbol, as if by `#undef name`, with `RC.EXE`'s `/u` or `windres`' `-U`.
+    ///
+    /// Definitions and undefinitions are passed to the compiler in the order they were first made,
+    /// so this only undoes preceding [`define()`](#method.define)s of the symbol,
+    /// as well as symbols predefined by the compiler, like `RC_INVOKED`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// extern crate embed_resource;
+    /// use embed_resource::Options;
+    ///
+    /// fn main() {
+    ///     // Compile checksums.rc without its debug-only resources, even if DEBUG is predefined
+    ///     embed_resource::compile_with("checksums.rc", Options::new().undefine("DEBUG")).unwrap();
+    /// }
+    /// ```
+    pub fn undefine<N: Into<String>>(mut self, name: N) -> Options {
+        self.symbols.push(Symbol::Undefine(name.into()));
+        self
+    }
+
+    /// Only compile the resources of the specified type, and of others specified this way, skipping the rest.
+    ///
+    /// The resource script is [parsed](fn.parse_resource_script.html), and compiled from a copy in `$OUT_DIR`, or the [temporary directory](#method.temp_dir),
+    /// with the statements declaring other resources blanked out, so the compiler's line numbers are still correct.
+    /// Resources declared in `#include`d files, or with types named by macros other than `windows.h`'s `RT_*`, can't be filtered.
+    /// Custom types are compared case-insensitively.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// extern crate embed_resource;
+    /// use embed_resource::{ResourceId, Options};
+    ///
+    /// fn main() {
+    ///     // Only embed the manifest and version information for debug builds
+    ///     let options = if cfg!(debug_assertions) {
+    ///         Options::new().only_resource_type(ResourceId::RT_MANIFEST).only_resource_type(ResourceId::RT_VERSION)
+    ///     } else {
+    ///         Options::new()
+    ///     };
+    ///     embed_resource::compile_with("app.rc", options).unwrap();
+    /// }
+    /// ```
+    pub fn only_resource_type(mut self, resource_type: ResourceId) -> Options {
+        if !self.only_types.contains(&resource_type) {
+            self.only_types.push(resource_type);
+        }
+        self
+    }
+
+    /// Skip the resources of the specified type, as described in [`only_resource_type()`](#method.only_resource_type).
+    ///
+    /// The group types (`RT_GROUP_ICON` and `RT_GROUP_CURSOR`) are what `ICON` and `CURSOR` statements declare.
+    pub fn skip_resource_type(mut self, resource_type: ResourceId) -> Options {
+        if !self.skipped_types.contains(&resource_type) {
+            self.skipped_types.push(resource_type);
+        }
+        self
+    }
+
+    /// Whether resources of the specified type are to be compiled
+    pub(crate) fn keeps_type(&self, resource_type: &ResourceId) -> bool {
+        let matches = |other: &ResourceId| match (resource_type, other) {
+            (ResourceId::Name(a), ResourceId::Name(b)) => a.eq_ignore_ascii_case(b),
+            _ => resource_type == other,
+        };
+        (self.only_types.is_empty() || self.only_types.iter().any(matches)) && !self.skipped_types.iter().any(matches)
+    }
+
+    /// After compiling, check that the output contains at least one resource of the specified type, failing otherwise.
+    ///
+    /// This catches resource scripts that silently don't include what they're supposed to,
+    /// like a manifest excluded by a stray `#ifdef`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// extern crate embed_resource;
+    /// use embed_resource::{ResourceId, Options};
+    ///
+    /// fn main() {
+    ///     embed_resource::compile_with("app.rc", Options::new()
+    ///         .expect_resource_type(ResourceId::RT_MANIFEST)
+    ///         .expect_resource_type(ResourceId::RT_VERSION)).unwrap();
+    /// }
+    /// ```
+    pub fn expect_resource_type(mut self, resource_type: ResourceId) -> Options {
+        if !self.expected_types.contains(&resource_type) {
+            self.expected_types.push(resource_type);
+        }
+        self
+    }
+
+    /// Pass `%INCLUDE%` and the Windows 10 SDK include directories to `RC.EXE` as explicit `/i` arguments,
+    /// instead of adding the latter to this process' `%INCLUDE%`, which is the default, unless `%INCLUDE%` has `windows.h` already.
+    ///
+    /// The directories are deduplicated and only the extant ones are passed.
+    /// This only affects MSVC, as `windres` doesn't use `%INCLUDE%`.
+    pub fn explicit_includes(mut self, explicit: bool) -> Options {
+        self.explicit_includes = Some(explicit);
+        self
+    }
+
+    /// Use the headers of the Windows 10+ SDK with the specified version, like `"10.0.19041.0"`,
+    /// passing its `Include\<version>\` `um`, `shared`, `ucrt`, and `winrt` directories to `RC.EXE` as `/i` arguments,
+    /// which take precedence over `%INCLUDE%`, and so over the headers of the SDK the Developer Command Prompt or discovery picked.
+    ///
+    /// The SDK is the one in the registry or `%WindowsSdkDir%`; compiling fails with
+    /// [`CompileError::MissingSdkIncludeVersion`](enum.CompileError.html#variant.MissingSdkIncludeVersion) if it doesn't have this version.
+    /// This only affects MSVC, as `windres` uses MinGW's headers.
+    pub fn sdk_include_version<V: Into<String>>(mut self, version: V) -> Options {
+        self.sdk_include_version = Some(version.into());
+        self
+    }
+
+    /// List the resources that ended up in the compiled output in [`Artifact::resources`](struct.Artifact.html#structfield.resources),
+    /// as read with [`read_compiled_resources()`](fn.read_compiled_resources.html), for example to check the final embedding in tests.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use embed_resource::{Options, ResourceId};
+    /// let artifact = embed_resource::compile_with("checksums.rc", Options::new().list_resources(true)).unwrap().unwrap();
+    /// for resource in &artifact.resources {
+    ///     println!("cargo:warning=embedded {} {} in language 0x{:04x}", resource.resource_type, resource.name, resource.language);
+    /// }
+    /// assert!(artifact.resources.iter().any(|r| r.resource_type == ResourceId::RT_VERSION));
+    /// ```
+    pub fn list_resources(mut self, list: bool) -> Options {
+        self.list_resources = Some(list);
+        self
+    }
+
+    /// Fail if the resource compiler reports any warnings, like duplicate resources or overlong strings.
+    ///
+    /// This requires capturing the compiler's output to parse it with
+    /// [`parse_diagnostics()`](fn.parse_diagnostics.html); it's replayed to the standard error stream afterwards.
+    pub fn warnings_as_errors(mut self, warnings_as_errors: bool) -> Options {
+        self.warnings_as_errors = Some(warnings_as_errors);
+        self
+    }
+
+    /// Ignore the resource compiler's warnings with the specified code, like `RC4005` (macro redefinition),
+    /// as if it hadn't reported them: they're left out of the replayed and [captured](enum.OutputMode.html) output,
+    /// and don't fail the compilation with [`warnings_as_errors()`](#method.warnings_as_errors).
+    ///
+    /// `RC.EXE` has no switches to disable its warnings, so they're filtered out of its output instead,
+    /// which requires capturing it, as with `warnings_as_errors()`.
+    /// Codes are letters followed by digits, compared case-insensitively; anything else fails with
+    /// [`CompileError::InvalidWarningCode`](enum.CompileError.html).
+    /// `windres`' warnings don't have codes, so this has no effect on them.
+    pub fn suppress_warning<C: Into<String>>(mut self, code: C) -> Options {
+        self.suppressed_warnings.push(code.into());
+        self
+    }
+
+    /// Whether the diagnostic is a warning suppressed with `suppress_warning()`
+    pub(crate) fn is_suppressed(&self, diagnostic: &Diagnostic) -> bool {
+        diagnostic.severity == Severity::Warning &&
+        diagnostic.code.as_ref().map(|code| self.suppressed_warnings.iter().any(|s| s.eq_ignore_ascii_case(code))).unwrap_or(false)
+    }
+
+    /// Null-terminate all strings in string tables, as with `RC.EXE /n`.
+    ///
+    /// By default string table entries are only length-prefixed, so `LoadStringW(.., 0)`-style direct access
+    /// yields strings that run into the next entry; with this set each entry is followed by a `NUL`
+    /// (which is included in its length), so the returned pointer can be used as a C string directly.
+    ///
+    /// This only affects MSVC, as `windres` has no equivalent.
+    pub fn null_terminate_strings(mut self, null_terminate: bool) -> Options {
+        self.null_terminate_strings = Some(null_terminate);
+        self
+    }
+
+    /// Specify the kind of image being built, which is an executable by default.
+    ///
+    /// For `CrateType::Cdylib` the compiled resource is linked with `cargo:rustc-cdylib-link-arg`,
+    /// so that it only ends up in the DLL and not, for example, in test executables,
+    /// and [`compile_manifest_with()`](fn.compile_manifest_with.html) uses the manifest ID DLLs are loaded with.
+    pub fn crate_type(mut self, crate_type: CrateType) -> Options {
+        self.crate_type = Some(crate_type);
+        self
+    }
+
+    /// Whether to print the `cargo:` directives linking the compiled resource, which is the default.
+    ///
+    /// Build systems other than cargo can turn them off and link the [`Artifact`](struct.Artifact.html) returned
+    /// by [`compile_with()`](fn.compile_with.html) themselves, or use [`compile_explicit()`](fn.compile_explicit.html),
+    /// which never prints any.
+    pub fn cargo_directives(mut self, emit: bool) -> Options {
+        self.suppress_directives = Some(!emit);
+        self
+    }
+
+    /// Whether to print `cargo:rerun-if-env-changed` for the environment variables read and `cargo:rerun-if-changed` for the resource script,
+    /// default `false`; see the [crate documentation](index.html#rerunning).
+    ///
+    /// Either turns off cargo's default of rerunning the build script when any file in the package changes,
+    /// so only turn them on if the build script emits its own for whatever else it reads.
+    pub fn rerun_directives(mut self, emit: bool) -> Options {
+        self.rerun_directives = Some(emit);
+        self
+    }
+
+    /// Instead of linking the compiled resource, export it to dependent crates as `links` metadata,
+    /// for crates that only compile resources shared by their siblings; see the [crate documentation](index.html#resource-crates).
+    ///
+    /// The `cargo:path`, `cargo:link_search`, `cargo:link_name`, and, if [emitted](#method.emit_res), `cargo:res_path` keys are printed,
+    /// which the build scripts of the crates depending on this one read as `$DEP_{LINKS}_PATH` &c. with [`link_exported()`](fn.link_exported.html),
+    /// as well as `cargo:resource-lib`, the same path as `cargo:path`, read as `$DEP_{LINKS}_RESOURCE_LIB` by those linking it themselves.
+    /// This only has an effect with [`cargo_directives()`](#method.cargo_directives) on.
+    pub fn export(mut self, export: bool) -> Options {
+        self.export = Some(export);
+        self
+    }
+
+    /// Write resource scripts generated by this crate, like by [`compile_manifest_with()`](fn.compile_manifest_with.html),
+    /// in the specified encoding, instead of the one most reliable for the resource compiler.
+    ///
+    /// This doesn't affect user-written resource scripts.
+    pub fn generated_script_encoding(mut self, encoding: ScriptEncoding) -> Options {
+        self.script_encoding = Some(encoding);
+        self
+    }
+
+    /// Skip running the resource compiler if the output already exists and nothing it depends on has changed.
+    ///
+    /// The compiler invocation and the contents of the resource script, of the files it `#include "..."`s, recursively,
+    /// and of the files its resources are read from are hashed, and the hash is stored next to the output as `{output}.hash`.
+    /// Files included with `<...>`, like `windows.h`, aren't hashed, nor is anything hidden behind macros,
+    /// so recompilation can be [forced](#method.force) if needed, also with `EMBED_RESOURCE_FORCE=1`.
+    ///
+    /// Builds sharing an output directory serialise on a lock file, `{output}.lock`, created exclusively and removed when done;
+    /// a build that finds it taken recompiles the resource without waiting, and without recording the hash.
+    /// Lock files left behind by killed builds are taken over after five minutes.
+    pub fn cache(mut self, cache: bool) -> Options {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Run the resource compiler even if the output is [up to date](#method.cache), then record the new output as such,
+    /// for example when debugging a resource that seems stale.
+    ///
+    /// [`compile_with()`](fn.compile_with.html) and co. also force recompilation if `$EMBED_RESOURCE_FORCE` is set to something other than `0`,
+    /// without needing to change the build script.
+    pub fn force(mut self, force: bool) -> Options {
+        self.force = Some(force);
+        self
+    }
+
+    /// With `windres`, make the linkable output, `lib{prefix}.a`, a thin archive referencing the compiled object, `{prefix}.o`,
+    /// instead of the object itself named like an archive, which is the default, and which linkers take whole,
+    /// for tools that need a real archive, like ones listing or merging them, without copying the object into it.
+    ///
+    /// Linkers only take the members of archives that something needs, and nothing needs resources,
+    /// so the archive is linked by path between `-Wl,--whole-archive` and `-Wl,--no-whole-archive` link arguments;
+    /// it also references the object by its absolute path, so [copies](#method.copy_artifact_to) only work as long as the output directory exists.
+    ///
+    /// This runs the `ar` next to `windres`, like `x86_64-w64-mingw32-ar`; if that fails, the output is the object itself, as usual.
+    /// MSVC's `LINK.EXE` doesn't read thin archives, so this has no effect there, nor with [`object()`](#method.object).
+    pub fn thin_archive(mut self, thin: bool) -> Options {
+        self.thin_archive = Some(thin);
+        self
+    }
+
+    /// Also keep the compiled resources as a `.res` file, for example for signing or packaging,
+    /// returned as [`Artifact::res_path`](struct.Artifact.html#structfield.res_path).
+    ///
+    /// The resource script is compiled once, to `{prefix}.res` in the output directory,
+    /// which is then copied (MSVC) or converted with `windres` to the linkable output.
+    pub fn emit_res(mut self, emit_res: bool) -> Options {
+        self.emit_res = Some(emit_res);
+        self
+    }
+
+    /// Compile to an object file, `{prefix}.o` for `windres` and `{prefix}.obj` for MSVC, instead of a library,
+    /// for custom link steps that take objects, linked with `cargo:rustc-link-arg` (or `cargo:rustc-cdylib-link-arg`).
+    ///
+    /// `windres` outputs COFF objects directly; with MSVC, the resource script is compiled to a `.res` file,
+    /// returned as [`Artifact::res_path`](struct.Artifact.html#structfield.res_path),
+    /// which is then converted with Visual Studio's `CVTRES.EXE`, found in `%PATH%` in Developer Command Prompts, or with vswhom otherwise.
+    pub fn object(mut self, object: bool) -> Options {
+        self.object = Some(object);
+        self
+    }
+
+    /// Also embed the resource script itself, for tracing shipped binaries back to their resource definitions,
+    /// as a resource of type `EMBED_RESOURCE_SOURCE` named after the script's [prefix](fn.default_prefix.html),
+    /// uppercased, with non-alphanumerics replaced with underscores, like `CHECKSUMS_V2` for `checksums-v2.rc`
+    /// (and an underscore prepended if it starts with a digit).
+    ///
+    /// The script is embedded as-is, in its own encoding, without `#include`d files.
+    /// This is done by compiling a copy in the output directory, or the [temporary directory](#method.temp_dir), with the declaration appended, instead of the script itself.
+    pub fn embed_source(mut self, embed: bool) -> Options {
+        self.embed_source = Some(embed);
+        self
+    }
+
+    /// Write the compiled resource to the specified file, instead of one named after the resource file in the output directory,
+    /// for integrating with packaging steps that expect it in a specific place.
+    ///
+    /// Relative paths are resolved against the current directory, since the linker doesn't run in it.
+    /// The file's directory is created if needed, and checked to be writable before compiling,
+    /// failing with [`CompileError::OutputDirNotWritable`](enum.CompileError.html#variant.OutputDirNotWritable) otherwise.
+    /// This is the [library, object](#method.object), or [thin archive](#method.thin_archive) returned as
+    /// [`Artifact::path`](struct.Artifact.html#structfield.path), and linked by that path, rather than by name;
+    /// intermediate files, like [`.res` files](#method.emit_res) and the objects thin archives reference, stay in the output directory.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use embed_resource::Options;
+    /// let artifact = embed_resource::compile_with("checksums.rc", Options::new().output_path("target/package/checksums.lib")).unwrap();
+    /// ```
+    pub fn output_path<P: Into<PathBuf>>(mut self, path: P) -> Options {
+        let path = path.into();
+        self.output_path = Some(env::current_dir().map(|cwd| cwd.join(&path)).unwrap_or(path));
+        self
+    }
+
+    /// Write the files this crate generates to compile from to the specified directory, created if needed, instead of the output directory,
+    /// for example to keep `$OUT_DIR` to just the compiled resources, or to put them on a faster filesystem.
+    ///
+    /// This applies to the resource scripts, manifests, and data generated by the functions taking `Options`,
+    /// like [`compile_manifest_with()`](fn.compile_manifest_with.html) and [`compile_bytes_with()`](fn.compile_bytes_with.html), and the copies of resource scripts
+    /// [filtered](#method.only_resource_type) or with their [source embedded](#method.embed_source).
+    /// The compiled resource, its [`.res` file](#method.emit_res), and the [cache](#method.cache)'s fingerprints stay in the output directory,
+    /// and, like those in it, the files in this directory aren't tracked with `cargo:rerun-if-changed`, since they're regenerated on every build.
+    ///
+    /// Relative paths are resolved against the current directory.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use embed_resource::{Manifest, Options};
+    /// # use std::env;
+    /// let temp_dir = env::temp_dir().join("my-crate-resources");
+    /// embed_resource::compile_manifest_with(&Manifest::new().utf8_code_page(true), Options::new().temp_dir(temp_dir)).unwrap();
+    /// ```
+    pub fn temp_dir<P: Into<PathBuf>>(mut self, dir: P) -> Options {
+        let dir = dir.into();
+        self.temp_dir = Some(env::current_dir().map(|cwd| cwd.join(&dir)).unwrap_or(dir));
+        self
+    }
+
+    /// After compiling, also copy the compiled resource, and the `.res` file if [emitted](#method.emit_res), into the specified directory,
+    /// created if needed, returned as [`Artifact::copy_path`](struct.Artifact.html#structfield.copy_path)
+    /// and [`Artifact::res_copy_path`](struct.Artifact.html#structfield.res_copy_path), for example for packaging.
+    ///
+    /// The copies are written to temporary files in the directory, then renamed over the destination,
+    /// so tools watching it never see partially written files.
+    /// The directory is resolved against the [base directory](#method.base_dir), if any.
+    /// Linking still uses the originals in the output directory.
+    pub fn copy_artifact_to<P: Into<PathBuf>>(mut self, dir: P) -> Options {
+        self.copy_to = Some(dir.into());
+        self
+    }
+
+    /// Retry running the resource compiler up to `retries` times if it fails transiently,
+    /// waiting `backoff`, then twice that, and so on, in between; the default is not to retry.
+    ///
+    /// Failures to spawn the compiler (other than it not being found) are transient,
+    /// as are failures where the compiler only complained about files being locked or inaccessible,
+    /// which happens on CI machines with aggressive antivirus or indexing;
+    /// any other errors, like ones in the resource script itself, fail immediately.
+    ///
+    /// This requires capturing the compiler's output, as with [`warnings_as_errors()`](#method.warnings_as_errors).
+    pub fn retry(mut self, retries: u32, backoff: Duration) -> Options {
+        self.retry = Some((retries, backoff));
+        self
+    }
+
+    /// Run the resource compiler with only the essential environment variables, instead of this process' entire environment,
+    /// so that the compiled resources can't depend on, for example, stray `%CL%` or `%RC%` variables.
+    ///
+    /// The essential variables are [`ESSENTIAL_ENV_VARS`](constant.ESSENTIAL_ENV_VARS.html), as set in this process
+    /// (which includes the Windows 10 SDK `%INCLUDE%` directories added by default):
+    /// `INCLUDE` for `RC.EXE`'s headers, `PATH` for `windres`' preprocessor, `TEMP` and `TMP` for temporary files,
+    /// and `SYSTEMROOT`, without which some Windows system libraries fail to load.
+    pub fn minimal_environment(mut self, minimal: bool) -> Options {
+        self.minimal_environment = Some(minimal);
+        self
+    }
+
+    /// On Windows hosts, run the resource compiler in a job object limiting its memory use, in bytes, and its CPU time,
+    /// so that a runaway compilation on a shared build machine fails instead of starving everything else;
+    /// the compiler is also killed with this process, even if it's killed outright, instead of being left behind.
+    ///
+    /// Going over the memory limit fails the compiler's allocations, and over the CPU time limit terminates it,
+    /// either way failing with [`CompileError::CompilerFailed`](enum.CompileError.html).
+    /// Failing to set up the job object fails with [`CompileError::Spawn`](enum.CompileError.html).
+    ///
+    /// This has no effect on other hosts.
+    pub fn limit_compiler(mut self, max_memory: Option<usize>, max_cpu_time: Option<Duration>) -> Options {
+        self.compiler_limits = Some((max_memory, max_cpu_time));
+        self
+    }
+
+    /// Specify what to do with the resource compiler's output.
+    ///
+    /// Diagnostics are still parsed for [`warnings_as_errors()`](#method.warnings_as_errors) and [`retry()`](#method.retry)
+    /// regardless, so `OutputMode::Silent` doesn't lose the warnings reported in [`CompileError::Warnings`](enum.CompileError.html).
+    pub fn output(mut self, output: OutputMode) -> Options {
+        self.output = Some(output);
+        self
+    }
+
+    /// If the resource compiler fails, try the others taking the same switches, in turn, using the first that succeeds,
+    /// for embedding resources with whatever is installed:
+    /// `llvm-rc` after `RC.EXE`, and `llvm-windres` after `windres`, run by name from `%PATH%`/`$PATH`.
+    /// MSVC targets don't fall back to `windres`, which takes different switches,
+    /// and reads neither the UTF-16 scripts generated for `RC.EXE` nor `%INCLUDE%`.
+    ///
+    /// Converting the compiled resource to an [object](#method.object), and making the [thin archive](#method.thin_archive),
+    /// use the tools matching whichever one succeeded.
+    ///
+    /// Which one compiled the resource is returned in [`Artifact::compiler`](struct.Artifact.html#structfield.compiler).
+    /// If they all fail, this fails with [`CompileError::AllCompilersFailed`](enum.CompileError.html#variant.AllCompilersFailed),
+    /// with each one's error.
+    pub fn try_alternative_compilers(mut self, try_alternatives: bool) -> Options {
+        self.try_alternatives = Some(try_alternatives);
+        self
+    }
+
+    /// Have the resource compiler report what it's doing, with `RC.EXE`'s and `llvm-rc`'s `/v` or `windres`' `--verbose`,
+    /// like which files it includes and how big the resources it writes are.
+    ///
+    /// With [`OutputMode::Captured`](enum.OutputMode.html#variant.Captured), the report of a successful compilation is returned in
+    /// [`Artifact::stdout`](struct.Artifact.html#structfield.stdout) and [`Artifact::stderr`](struct.Artifact.html#structfield.stderr),
+    /// for tools reporting what was embedded; it's empty if the output was [up to date](#method.cache) and nothing was run.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use embed_resource::{Options, OutputMode};
+    /// let artifact = embed_resource::compile_with("checksums.rc", Options::new().verbose(true).output(OutputMode::Captured)).unwrap().unwrap();
+    /// for line in artifact.stdout.lines().filter(|l| l.starts_with("Writing")) {
+    ///     println!("cargo:warning={}", line);
+    /// }
+    /// ```
+    pub fn verbose(mut self, verbose: bool) -> Options {
+        self.verbose = Some(verbose);
+        self
+    }
+
+    /// Whether [`compile_manifest_with()`](fn.compile_manifest_with.html) should pass `/MANIFEST:NO` to `LINK.EXE` for MSVC targets.
+    ///
+    /// `LINK.EXE` generates a manifest of its own when passed `/MANIFEST`, and, if it's embedded (with `/MANIFEST:EMBED`),
+    /// it conflicts with the one embedded as a resource, failing the link with duplicate resource errors like `CVT1100` and `LNK1123`,
+    /// or silently merging with it (`LNK4078`).
+    /// If this isn't specified, and either is passed in the `RUSTFLAGS`, a `cargo:warning` recommending it is printed instead;
+    /// link arguments from other build scripts can't be seen.
+    pub fn disable_linker_manifest(mut self, disable: bool) -> Options {
+        self.disable_linker_manifest = Some(disable);
+        self
+    }
+
+    /// Make the linked resources reproducible, as far as possible; see the [crate documentation](index.html#reproducibility).
+    ///
+    /// For MSVC targets, this passes `/Brepro` to `LINK.EXE`, which otherwise timestamps the converted resources,
+    /// and makes the whole binary reproducible, with its timestamp replaced by a hash.
+    /// If `$SOURCE_DATE_EPOCH` is set, it's also defined for the resource script, to use instead of `__DATE__` and `__TIME__`.
+    pub fn reproducible(mut self, reproducible: bool) -> Options {
+        self.reproducible = Some(reproducible);
+        self
+    }
+
+    /// Declare the resources in the specified language, as a `LANGID` like `0x0407` for German (Germany),
+    /// up to the first `LANGUAGE` statement, instead of U.S. English (`0x0409`), with `RC.EXE`'s `/l` or `windres`' `--language`.
+    ///
+    /// This also applies to the resource scripts generated by [`compile_manifest_with()`](fn.compile_manifest_with.html)
+    /// and [`compile_resource_entries_with()`](fn.compile_resource_entries_with.html), for example for localized icons;
+    /// version information is localized with [`VersionInfo::language()`](struct.VersionInfo.html#method.language) instead.
+    ///
+    /// Neutral primary languages with a non-neutral sublanguage, like `LANG_USER_DEFAULT` (`0x0400`), only make sense at runtime,
+    /// and fail with [`CompileError::InvalidLanguage`](enum.CompileError.html).
+    pub fn language(mut self, langid: u16) -> Options {
+        self.language = Some(langid);
+        self
+    }
+
+    /// Read the resource script in the specified codepage, like 65001 for UTF-8, instead of the system's ANSI one,
+    /// with `RC.EXE`'s `/c` or `windres`' `--codepage`.
+    ///
+    /// If this isn't specified and the resource script starts with a UTF-8 BOM, UTF-8 is used,
+    /// since `RC.EXE` would otherwise fail on the BOM, which is reported in [`Artifact::detected_codepage`](struct.Artifact.html#structfield.detected_codepage),
+    /// and with a `cargo:warning` from [`compile_with()`](fn.compile_with.html) and co.
+    ///
+    /// This is the codepage of the script as a whole; `#pragma code_page()` still changes it for the rest of the file.
+    pub fn codepage(mut self, codepage: u16) -> Options {
+        self.codepage = Some(codepage);
+        self
+    }
+
+    /// Select the MSVC tools for the specified architecture, instead of the one of the target triple (`$TARGET` for [`compile()`](fn.compile.html) and co.),
+    /// for compiling a resource for a helper of another architecture in the same build.
+    ///
+    /// This picks the `bin` directory of the Windows SDK `RC.EXE` is found in, whose `%INCLUDE%` headers it's run with,
+    /// and the `/machine` `CVTRES.EXE` converts [objects](#method.object) for.
+    /// `windres` compiles for the architecture of the MinGW toolchain it belongs to; use [`linker()`](#method.linker) to pick another one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use embed_resource::{Arch, Options, detect_arch};
+    /// // The 32-bit updater, next to the crate being built for x64
+    /// let arch = detect_arch("i686-pc-windows-msvc").unwrap_or(Arch::X86);
+    /// embed_resource::compile_with("updater.rc", Options::new().arch(arch).object(true)).unwrap();
+    /// ```
+    pub fn arch(mut self, arch: Arch) -> Options {
+        self.arch = Some(arch);
+        self
+    }
+
+    /// Use the `windres` belonging to the same MinGW toolchain as the specified linker, for `-gnu` targets,
+    /// instead of the one found by name in `%PATH%`/`$PATH`.
+    ///
+    /// For a linker named `<prefix>-gcc`, like `/opt/mingw/bin/x86_64-w64-mingw32-gcc`, this is `<prefix>-windres` next to it,
+    /// if it exists; otherwise the usual one is used.
+    /// [`compile_with()`](fn.compile_with.html) and co. default to the linker cargo configured for the target (`$RUSTC_LINKER`), if any.
+    pub fn linker<P: Into<PathBuf>>(mut self, linker: P) -> Options {
+        self.linker = Some(linker.into());
+        self
+    }
+
+    /// Record each resource compiler invocation in the specified `compile_commands.json`-style database.
+    ///
+    /// Each record has the working `directory`, the resource `file`, the `arguments` (starting with the fully-resolved compiler),
+    /// and the `output`; records for the same resource in the same directory are replaced, all others are kept.
+    /// The database is written with one record per line, and only lines starting with `{` are preserved from an existing one,
+    /// so it shouldn't be shared with tools writing it differently.
+    pub fn record_compile_commands<P: Into<PathBuf>>(mut self, database: P) -> Options {
+        self.compile_commands = Some(database.into());
+        self
+    }
+
+    /// Define the package version from the environment variables cargo sets for build scripts,
+    /// so that hand-written resource scripts can use it, for example, as `FILEVERSION CARGO_PKG_VERSION_MAJOR, CARGO_PKG_VERSION_MINOR, ...`.
+    ///
+    /// `CARGO_PKG_VERSION_MAJOR`, `CARGO_PKG_VERSION_MINOR`, and `CARGO_PKG_VERSION_PATCH` are defined as numbers,
+    /// and `CARGO_PKG_VERSION` and `CARGO_PKG_VERSION_PRE` as string literals, like `"1.2.3-beta.1"` and `"beta.1"`.
+    /// As with environment variable definitions, [`define()`](#method.define) takes precedence,
+    /// and [`compile_explicit()`](fn.compile_explicit.html) ignores this.
+    pub fn cargo_version_defines(mut self, define: bool) -> Options {
+        self.cargo_version_defines = Some(define);
+        self
+    }
+
+    /// Resolve relative resource file paths against the specified directory, and add it to the include path,
+    /// so that files referenced by the resource script, like icons, are found regardless of the working directory.
+    ///
+    /// A relative base directory is made absolute against the working directory at the time of the call.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// extern crate embed_resource;
+    /// use embed_resource::Options;
+    ///
+    /// fn main() {
+    ///     // Compiles res/app.rc, which can reference res/app.ico as "app.ico"
+    ///     embed_resource::compile_with("app.rc", Options::new().base_dir("res")).unwrap();
+    /// }
+    /// ```
+    pub fn base_dir<P: Into<PathBuf>>(mut self, dir: P) -> Options {
+        let dir = dir.into();
+        self.base_dir = Some(env::current_dir().map(|cwd| cwd.join(&dir)).unwrap_or(dir));
+        self
+    }
+
+    /// Add the specified directory to the include path, after the [base directory](#method.base_dir) and previously added ones.
+    ///
+    /// This is meant for headers generated by the build script, like a `resource.h` with the IDs the Rust code also uses.
+    /// They don't need `cargo:rerun-if-changed`, since they're rewritten whenever the build script runs,
+    /// but the [cache](#method.cache) does find `#include`s in these directories, and recompiles if they change.
+    ///
+    /// # Examples
+    ///
+    /// In your build script:
+    ///
+    /// ```rust,no_run
+    /// extern crate embed_resource;
+    /// use embed_resource::Options;
+    /// use std::path::Path;
+    /// use std::{env, fs};
+    ///
+    /// fn main() {
+    ///     // app.rc contains #include "resource.h" and 1 DIALOGEX IDD_ABOUT ...
+    ///     let out_dir = env::var("OUT_DIR").unwrap();
+    ///     fs::write(Path::new(&out_dir).join("resource.h"), "#define IDD_ABOUT 101\n").unwrap();
+    ///     embed_resource::compile_with("app.rc", Options::new().include_dir(&out_dir).cache(true)).unwrap();
+    /// }
+    /// ```
+    pub fn include_dir<P: Into<PathBuf>>(mut self, dir: P) -> Options {
+        let dir = dir.into();
+        if !self.include_dirs.contains(&dir) {
+            self.include_dirs.push(dir);
+        }
+        self
+    }
+
+    /// Expand `$(name)` to the specified value in the resource file path
+    /// and the files referenced by generated resource scripts, like [`compile_resource_entries_with()`](fn.compile_resource_entries_with.html)'s,
+    /// for resource setups carried over from MSBuild, which uses `$(ProjectDir)`-style properties.
+    ///
+    /// Names are compared case-insensitively, as in MSBuild, and redefining a variable replaces its previous value.
+    /// Once any variable is set, a `$(name)` of one that isn't fails with
+    /// [`CompileError::UnknownPathVariable`](enum.CompileError.html#variant.UnknownPathVariable);
+    /// paths in the resource script itself aren't expanded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use embed_resource::Options;
+    /// # use std::env;
+    /// let options = Options::new()
+    ///     .path_variable("ProjectDir", env::var_os("CARGO_MANIFEST_DIR").unwrap())
+    ///     .path_variable("Configuration", if cfg!(debug_assertions) { "Debug" } else { "Release" });
+    /// embed_resource::compile_with("$(ProjectDir)/res/$(Configuration)/app.rc", options).unwrap();
+    /// ```
+    pub fn path_variable<N: Into<String>, V: Into<PathBuf>>(mut self, name: N, value: V) -> Options {
+        let (name, value) = (name.into(), value.into());
+        match self.path_variables.iter().position(|(n, _)| n.eq_ignore_ascii_case(&name)) {
+            Some(i) => self.path_variables[i].1 = value,
+            None => self.path_variables.push((name, value)),
+        }
+        self
+    }
+
+    /// Layer the other options over these, as when combining project-wide defaults with per-resource overrides.
+    ///
+    /// What's set in `other` takes precedence, even if it's set to the default, and what's left unset in `other` keeps the value set here:
+    ///   * symbols are defined and undefined, include directories, path variables, and resource types added, and warnings suppressed,
+    ///     in order, as if by calling the corresponding methods on these options,
+    ///   * and everything else set in `other`, like the [codepage](#method.codepage), [output mode](#method.output),
+    ///     or whether to [cache](#method.cache), replaces the value here, so overrides can turn switches off again.
+    ///
+    /// [`compile()`](fn.compile.html) and co. then layer the options derived from the environment under the result:
+    /// `EMBED_RESOURCE_DEFINE_*`s and [cargo's version variables](#method.cargo_version_defines) only define symbols
+    /// not defined or undefined programmatically, `$RUSTC_LINKER` only applies if no [linker](#method.linker) was specified,
+    /// and `$EMBED_RESOURCE_FORCE` can [force recompilation](#method.force) but not prevent it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use embed_resource::{Options, OutputMode};
+    /// let defaults = Options::new().define("COMPANY", Some("\"Checksum Enthusiasts\"")).include_dir("res/include").codepage(65001);
+    /// let merged = defaults.clone().merge(Options::new().define("COMPANY", Some("\"CE Ltd.\"")).output(OutputMode::Silent));
+    /// assert_eq!(merged, defaults.define("COMPANY", Some("\"CE Ltd.\"")).output(OutputMode::Silent));
+    /// ```
+    pub fn merge(mut self, other: Options) -> Options {
+        let Options { symbols,
+                      expected_types,
+                      only_types,
+                      skipped_types,
+                      explicit_includes,
+                      warnings_as_errors,
+                      compile_commands,
+                      null_terminate_strings,
+                      crate_type,
+                      suppress_directives,
+                      rerun_directives,
+                      script_encoding,
+                      cache,
+                      emit_res,
+                      retry,
+                      minimal_environment,
+                      output,
+                      disable_linker_manifest,
+                      cargo_version_defines,
+                      base_dir,
+                      include_dirs,
+                      reproducible,
+                      linker,
+                      codepage,
+                      compiler_limits,
+                      copy_to,
+                      object,
+                      suppressed_warnings,
+                      force,
+                      export,
+                      language,
+                      embed_source,
+                      sdk_include_version,
+                      thin_archive,
+                      list_resources,
+                      arch,
+                      path_variables,
+                      output_path,
+                      verbose,
+                      try_alternatives,
+                      temp_dir } = other;
+
+        for symbol in symbols {
+            self = match symbol {
+                Symbol::Define(name, value) => self.define(name, value.as_ref().map(|v| v.as_str())),
+                Symbol::Undefine(name) => self.undefine(name),
+            };
+        }
+        for resource_type in expected_types {
+            self = self.expect_resource_type(resource_type);
+        }
+        for resource_type in only_types {
+            self = self.only_resource_type(resource_type);
+        }
+        for resource_type in skipped_types {
+            self = self.skip_resource_type(resource_type);
+        }
+        for code in suppressed_warnings {
+            self = self.suppress_warning(code);
+        }
+        for dir in include_dirs {
+            self = self.include_dir(dir);
+        }
+        for (name, value) in path_variables {
+            self = self.path_variable(name, value);
+        }
+
+        self.explicit_includes = explicit_includes.or(self.explicit_includes);
+        self.warnings_as_errors = warnings_as_errors.or(self.warnings_as_errors);
+        self.null_terminate_strings = null_terminate_strings.or(self.null_terminate_strings);
+        self.crate_type = crate_type.or(self.crate_type);
+        self.suppress_directives = suppress_directives.or(self.suppress_directives);
+        self.rerun_directives = rerun_directives.or(self.rerun_directives);
+        self.cache = cache.or(self.cache);
+        self.emit_res = emit_res.or(self.emit_res);
+        self.retry = retry.or(self.retry);
+        self.minimal_environment = minimal_environment.or(self.minimal_environment);
+        self.output = output.or(self.output);
+        self.cargo_version_defines = cargo_version_defines.or(self.cargo_version_defines);
+        self.reproducible = reproducible.or(self.reproducible);
+        self.object = object.or(self.object);
+        self.force = force.or(self.force);
+        self.export = export.or(self.export);
+        self.embed_source = embed_source.or(self.embed_source);
+        self.thin_archive = thin_archive.or(self.thin_archive);
+        self.list_resources = list_resources.or(self.list_resources);
+        self.verbose = verbose.or(self.verbose);
+        self.try_alternatives = try_alternatives.or(self.try_alternatives);
+        self.compile_commands = compile_commands.or(self.compile_commands);
+        self.script_encoding = script_encoding.or(self.script_encoding);
+        self.disable_linker_manifest = disable_linker_manifest.or(self.disable_linker_manifest);
+        self.base_dir = base_dir.or(self.base_dir);
+        self.linker = linker.or(self.linker);
+        self.codepage = codepage.or(self.codepage);
+        self.compiler_limits = compiler_limits.or(self.compiler_limits);
+        self.copy_to = copy_to.or(self.copy_to);
+        self.language = language.or(self.language);
+        self.sdk_include_version = sdk_include_version.or(self.sdk_include_version);
+        self.arch = arch.or(self.arch);
+        self.output_path = output_path.or(self.output_path);
+        self.temp_dir = temp_dir.or(self.temp_dir);
+        self
+    }
+
+    /// The base directory, if any, and the include directories, in search order
+    pub(crate) fn search_dirs(&self) -> impl Iterator<Item = &PathBuf> {
+        self.base_dir.iter().chain(&self.include_dirs)
+    }
+
+    /// The resource file path, with the path variables expanded, relative to the base directory, if any
+    pub(crate) fn resolve(&self, resource: &Path) -> Result<PathBuf, CompileError> {
+        let resource = self.expand_path(resource)?;
+        match self.base_dir {
+            Some(ref base_dir) => Ok(base_dir.join(resource)),
+            None => Ok(resource),
+        }
+    }
+
+    /// The path with each `$(name)` replaced with the value of the path variable, if any are set;
+    /// an unterminated `$(` is an unknown variable named by the rest of the path
+    pub(crate) fn expand_path(&self, path: &Path) -> Result<PathBuf, CompileError> {
+        let mut rest = match path.to_str() {
+            Some(path) if !self.path_variables.is_empty() => path,
+            _ => return Ok(path.to_path_buf()),
+        };
+        let mut ret = OsString::new();
+        while let Some(start) = rest.find("$(") {
+            let name = &rest[start + 2..];
+            let name = match name.find(')') {
+                Some(end) => &name[..end],
+                // Unterminated, so not a variable, even if it's named like one
+                None => return Err(CompileError::UnknownPathVariable(name.to_string(), path.to_path_buf())),
+            };
+            let value = &self.path_variables
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .ok_or_else(|| CompileError::UnknownPathVariable(name.to_string(), path.to_path_buf()))?
+                .1;
+            ret.push(&rest[..start]);
+            ret.push(value);
+            rest = &rest[start + 2 + name.len() + 1..];
+        }
+        ret.push(rest);
+        Ok(PathBuf::from(ret))
+    }
+
+    /// Force recompilation if `$EMBED_RESOURCE_FORCE` is set to something other than `0`.
+    pub(crate) fn with_env_force(mut self) -> Options {
+        if env_flag("EMBED_RESOURCE_FORCE") {
+            self.force = Some(true);
+        }
+        self
+    }
+
+    /// Default to the linker cargo configured for the target, if any.
+    pub(crate) fn with_env_linker(mut self) -> Options {
+        if self.linker.is_none() {
+            self.linker = env::var_os("RUSTC_LINKER").map(PathBuf::from);
+        }
+        self
+    }
+
+    /// Add definitions from `EMBED_RESOURCE_DEFINE_`-prefixed environment variables,
+    /// and, if requested, cargo's package version variables and `SOURCE_DATE_EPOCH`, not already defined or undefined programmatically.
+    ///
+    /// An empty value defines the symbol without a value; variables with non-UTF-8 names or values are ignored.
+    pub(crate) fn with_env_defines(mut self) -> Options {
+        let mut env_defines: Vec<_> = env::vars_os().filter_map(|(k, v)| env_define(k, v)).collect();
+        env_defines.sort();
+        if self.cargo_version_defines.unwrap_or(false) {
+            for &(var, string) in &[("CARGO_PKG_VERSION", true),
+                                    ("CARGO_PKG_VERSION_MAJOR", false),
+                                    ("CARGO_PKG_VERSION_MINOR", false),
+                                    ("CARGO_PKG_VERSION_PATCH", false),
+                                    ("CARGO_PKG_VERSION_PRE", true)] {
+                if let Ok(value) = env::var(var) {
+                    env_defines.push((var.to_string(), Some(if string { format!("\"{}\"", value) } else { value })));
+                }
+            }
+        }
+
+        if self.reproducible.unwrap_or(false) {
+            if let Ok(epoch) = env::var("SOURCE_DATE_EPOCH") {
+                env_defines.push(("SOURCE_DATE_EPOCH".to_string(), Some(epoch)));
+            }
+        }
+
+        for (name, value) in env_defines {
+            if !self.symbols.iter().any(|s| s.name() == name) {
+                self.symbols.push(Symbol::Define(name, value));
+            }
+        }
+        self
+    }
+
+}
+
+
+/// A preprocessor symbol to define or undefine.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub(crate) enum Symbol {
+    Define(String, Option<String>),
+    Undefine(String),
+}
+
+impl Symbol {
+    pub(crate) fn name(&self) -> &str {
+        match *self {
+            Symbol::Define(ref name, _) | Symbol::Undefine(ref name) => name,
+        }
+    }
+
+    /// The definition in `NAME` or `NAME=VALUE` form, as accepted by both `RC.EXE` and `windres`, or the name to undefine
+    pub(crate) fn argument(&self) -> String {
+        match *self {
+            Symbol::Define(ref name, Some(ref value)) => format!("{}={}", name, value),
+            Symbol::Define(ref name, None) | Symbol::Undefine(ref name) => name.clone(),
+        }
+    }
+}
+
+fn env_define(key: OsString, value: OsString) -> Option<(String, Option<String>)> {
+    let key = key.into_string().ok()?;
+    if !key.starts_with(ENV_DEFINE_PREFIX) || key.len() == ENV_DEFINE_PREFIX.len() {
+        return None;
+    }
+
+    let value = value.into_string().ok()?;
+    Some((key[ENV_DEFINE_PREFIX.len()..].to_string(), if value.is_empty() { None } else { Some(value) }))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::CompileError;
+    use super::{CrateType, OutputMode, Options, Symbol};
+    use std::path::{PathBuf, Path};
+    use std::time::Duration;
+
+    #[test]
+    fn expand_path_variables() {
+        let options = Options::new().path_variable("ProjectDir", "/src/app").path_variable("Configuration", "Release");
+        assert_eq!(options.expand_path(Path::new("$(projectdir)/res/$(Configuration)/app.rc")).unwrap(),
+                   PathBuf::from("/src/app/res/Release/app.rc"));
+        assert_eq!(options.expand_path(Path::new("res/app.rc")).unwrap(), PathBuf::from("res/app.rc"));
+        match options.expand_path(Path::new("$(SolutionDir)/app.rc")) {
+            Err(CompileError::UnknownPathVariable(ref name, _)) => assert_eq!(name, "SolutionDir"),
+            ret => panic!("{:?}", ret),
+        }
+    }
+
+    #[test]
+    fn expand_path_unterminated_variable() {
+        let options = Options::new().path_variable("ProjectDir", "/src/app");
+        for path in &["$(ProjectDir", "res/$(ProjectDir/app.rc", "$(ProjectDir)/$(ProjectDir"] {
+            match options.expand_path(Path::new(path)) {
+                Err(CompileError::UnknownPathVariable(..)) => {}
+                ret => panic!("{}: {:?}", path, ret),
+            }
+        }
+    }
+
+    #[test]
+    fn merge_overrides_switches_either_way() {
+        let base = Options::new().cache(true).force(true).thin_archive(true).crate_type(CrateType::Cdylib).output(OutputMode::Silent);
+        let merged = base.merge(Options::new().cache(false).force(false).thin_archive(false).crate_type(CrateType::Bin).output(OutputMode::Inherit));
+        assert_eq!(merged.cache, Some(false));
+        assert_eq!(merged.force, Some(false));
+        assert_eq!(merged.thin_archive, Some(false));
+        assert_eq!(merged.crate_type, Some(CrateType::Bin));
+        assert_eq!(merged.output, Some(OutputMode::Inherit));
+
+        let merged = Options::new().object(false).merge(Options::new().object(true));
+        assert_eq!(merged.object, Some(true));
+    }
+
+    #[test]
+    fn merge_keeps_what_is_unset() {
+        let base = Options::new()
+            .cache(true)
+            .crate_type(CrateType::Cdylib)
+            .output(OutputMode::Captured)
+            .retry(3, Duration::from_millis(100))
+            .codepage(65001);
+        assert_eq!(base.clone().merge(Options::new()), base);
+
+        let merged = base.merge(Options::new().retry(1, Duration::from_millis(5)).codepage(1252));
+        assert_eq!(merged.cache, Some(true));
+        assert_eq!(merged.crate_type, Some(CrateType::Cdylib));
+        assert_eq!(merged.retry, Some((1, Duration::from_millis(5))));
+        assert_eq!(merged.codepage, Some(1252));
+    }
+
+    #[test]
+    fn merge_accumulates_in_order() {
+        let base = Options::new().define("A", Some("1")).define("B", None).include_dir("base");
+        let merged = base.merge(Options::new().undefine("B").define("A", Some("2")).define("C", None).include_dir("override"));
+        assert_eq!(merged.symbols,
+                   [Symbol::Define("A".to_string(), Some("2".to_string())),
+                    Symbol::Define("B".to_string(), None),
+                    Symbol::Undefine("B".to_string()),
+                    Symbol::Define("C".to_string(), None)]);
+        assert_eq!(merged.include_dirs, [PathBuf::from("base"), PathBuf::from("override")]);
+    }
+}
@@ -0,0 +1,17 @@
+//! The commonly used types and functions, for a single glob import.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! extern crate embed_resource;
+//! use embed_resource::prelude::*;
+//!
+//! fn main() {
+//!     let artifact: Result<Option<Artifact>, CompileError> = compile_with("checksums.rc", Options::new().output(OutputMode::Captured));
+//!     artifact.unwrap();
+//! }
+//! ```
+
+
+pub use super::{Options, OutputMode, CrateType, ResourceId, Artifact, CompileError, Arch};
+pub use super::{compile, compile_with, compile_explicit, compile_manifest_with};
@@ -0,0 +1,355 @@
+use std::io::{self, Error as IoError, ErrorKind as IoErrorKind};
+use std::path::Path;
+use std::fmt;
+use std::fs;
+
+
+/// A resource type or name: either a 16-bit integer ordinal or a string.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ResourceId {
+    Ordinal(u16),
+    Name(String),
+}
+
+impl ResourceId {
+    pub const RT_CURSOR: ResourceId = ResourceId::Ordinal(1);
+    pub const RT_BITMAP: ResourceId = ResourceId::Ordinal(2);
+    pub const RT_ICON: ResourceId = ResourceId::Ordinal(3);
+    pub const RT_MENU: ResourceId = ResourceId::Ordinal(4);
+    pub const RT_DIALOG: ResourceId = ResourceId::Ordinal(5);
+    pub const RT_STRING: ResourceId = ResourceId::Ordinal(6);
+    pub const RT_FONTDIR: ResourceId = ResourceId::Ordinal(7);
+    pub const RT_FONT: ResourceId = ResourceId::Ordinal(8);
+    pub const RT_ACCELERATOR: ResourceId = ResourceId::Ordinal(9);
+    pub const RT_RCDATA: ResourceId = ResourceId::Ordinal(10);
+    pub const RT_MESSAGETABLE: ResourceId = ResourceId::Ordinal(11);
+    pub const RT_GROUP_CURSOR: ResourceId = ResourceId::Ordinal(12);
+    pub const RT_GROUP_ICON: ResourceId = ResourceId::Ordinal(14);
+    pub const RT_VERSION: ResourceId = ResourceId::Ordinal(16);
+    pub const RT_DLGINCLUDE: ResourceId = ResourceId::Ordinal(17);
+    pub const RT_PLUGPLAY: ResourceId = ResourceId::Ordinal(19);
+    pub const RT_VXD: ResourceId = ResourceId::Ordinal(20);
+    pub const RT_ANICURSOR: ResourceId = ResourceId::Ordinal(21);
+    pub const RT_ANIICON: ResourceId = ResourceId::Ordinal(22);
+    pub const RT_HTML: ResourceId = ResourceId::Ordinal(23);
+    pub const RT_MANIFEST: ResourceId = ResourceId::Ordinal(24);
+}
+
+impl fmt::Display for ResourceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ResourceId::Ordinal(o) => write!(f, "{}", o),
+            ResourceId::Name(ref n) => f.write_str(n),
+        }
+    }
+}
+
+
+/// A single resource found in a compiled resource file.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CompiledResource {
+    pub resource_type: ResourceId,
+    pub name: ResourceId,
+    pub language: u16,
+}
+
+
+/// List the resources contained in a compiled resource file.
+///
+/// Both `RC.EXE`-produced `.res` files and `windres`/`CVTRES.EXE`-produced COFF objects are understood;
+/// only the resource headers/directories are read, the resource data itself is not validated.
+pub fn read_compiled_resources<P: AsRef<Path>>(path: P) -> io::Result<Vec<CompiledResource>> {
+    let data = fs::read(path)?;
+    if data.starts_with(RES_SIGNATURE) {
+        parse_res(&data)
+    } else {
+        parse_coff(&data)
+    }.ok_or_else(|| IoError::new(IoErrorKind::InvalidData, "not a well-formed .res file or COFF object with a .rsrc section"))
+}
+
+
+/// The empty resource every `.res` file starts with
+const RES_SIGNATURE: &[u8] = &[0x00, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00];
+
+fn parse_res(data: &[u8]) -> Option<Vec<CompiledResource>> {
+    let mut ret = vec![];
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let data_size = u32_at(data, pos)? as usize;
+        let header_end = pos.checked_add(u32_at(data, pos + 4)? as usize)?;
+        let data_end = header_end.checked_add(data_size)?;
+        if data_end > data.len() {
+            return None;
+        }
+
+        let (resource_type, name_pos) = res_id_at(&data[..header_end], pos + 8)?;
+        let (name, tail_pos) = res_id_at(&data[..header_end], name_pos)?;
+        // DataVersion, MemoryFlags, then LanguageId, DWORD-aligned
+        let language = u16_at(&data[..header_end], align4(tail_pos) + 6)?;
+
+        if data_size != 0 || resource_type != ResourceId::Ordinal(0) {
+            ret.push(CompiledResource {
+                resource_type,
+                name,
+                language,
+            });
+        }
+
+        pos = align4(data_end);
+    }
+
+    Some(ret)
+}
+
+fn res_id_at(data: &[u8], pos: usize) -> Option<(ResourceId, usize)> {
+    if u16_at(data, pos)? == 0xFFFF {
+        Some((ResourceId::Ordinal(u16_at(data, pos + 2)?), pos + 4))
+    } else {
+        let mut name = vec![];
+        let mut pos = pos;
+        loop {
+            let c = u16_at(data, pos)?;
+            pos += 2;
+            if c == 0 {
+                break;
+            }
+            name.push(c);
+        }
+        Some((ResourceId::Name(String::from_utf16_lossy(&name)), pos))
+    }
+}
+
+fn parse_coff(data: &[u8]) -> Option<Vec<CompiledResource>> {
+    let section_count = u16_at(data, 2)? as usize;
+    let sections_start = 20 + u16_at(data, 16)? as usize;
+
+    let rsrc = (0..section_count).map(|i| sections_start + i * 40).find(|&s| data.get(s..s + 8).map(|n| n.starts_with(b".rsrc")).unwrap_or(false))?;
+    let rsrc_start = u32_at(data, rsrc + 20)? as usize;
+    let rsrc = data.get(rsrc_start..rsrc_start.checked_add(u32_at(data, rsrc + 16)? as usize)?)?;
+
+    // Resource directories are three levels deep: type, name, language
+    let mut ret = vec![];
+    for (resource_type, names) in coff_directory(rsrc, 0)? {
+        for (name, languages) in coff_directory(rsrc, subdirectory(names)?)? {
+            for (language, _) in coff_directory(rsrc, subdirectory(languages)?)? {
+                ret.push(CompiledResource {
+                    resource_type: resource_type.clone(),
+                    name: name.clone(),
+                    language: match language {
+                        ResourceId::Ordinal(l) => l,
+                        ResourceId::Name(_) => return None,
+                    },
+                });
+            }
+        }
+    }
+    Some(ret)
+}
+
+/// Read an `IMAGE_RESOURCE_DIRECTORY` and its entries' IDs and `OffsetToData`s
+fn coff_directory(rsrc: &[u8], pos: usize) -> Option<Vec<(ResourceId, u32)>> {
+    let entries = u16_at(rsrc, pos + 12)? as usize + u16_at(rsrc, pos + 14)? as usize;
+    (0..entries)
+        .map(|i| {
+            let entry = pos + 16 + i * 8;
+            let name = u32_at(rsrc, entry)?;
+            let id = if name & 0x8000_0000 != 0 {
+                let name_pos = (name & 0x7FFF_FFFF) as usize;
+                let name_len = u16_at(rsrc, name_pos)? as usize;
+                let name = (0..name_len).map(|c| u16_at(rsrc, name_pos + 2 + c * 2)).collect::<Option<Vec<_>>>()?;
+                ResourceId::Name(String::from_utf16_lossy(&name))
+            } else {
+                ResourceId::Ordinal(name as u16)
+            };
+            Some((id, u32_at(rsrc, entry + 4)?))
+        })
+        .collect()
+}
+
+fn subdirectory(offset_to_data: u32) -> Option<usize> {
+    if offset_to_data & 0x8000_0000 != 0 {
+        Some((offset_to_data & 0x7FFF_FFFF) as usize)
+    } else {
+        None
+    }
+}
+
+fn align4(pos: usize) -> usize {
+    (pos + 3) & !3
+}
+
+fn u16_at(data: &[u8], pos: usize) -> Option<u16> {
+    data.get(pos..pos.checked_add(2)?).map(|b| u16::from(b[0]) | u16::from(b[1]) << 8)
+}
+
+fn u32_at(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from(u16_at(data, pos)?) | u32::from(u16_at(data, pos + 2)?) << 16)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{CompiledResource, ResourceId, RES_SIGNATURE, parse_res, parse_coff};
+
+    fn ordinal(id: u16) -> Vec<u8> {
+        vec![0xFF, 0xFF, id as u8, (id >> 8) as u8]
+    }
+
+    fn utf16(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(|c| vec![c as u8, (c >> 8) as u8]).collect()
+    }
+
+    fn name(s: &str) -> Vec<u8> {
+        let mut ret = utf16(s);
+        ret.extend_from_slice(&[0, 0]);
+        ret
+    }
+
+    fn le32(v: u32) -> Vec<u8> {
+        vec![v as u8, (v >> 8) as u8, (v >> 16) as u8, (v >> 24) as u8]
+    }
+
+    fn pad4(data: &mut Vec<u8>) {
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+    }
+
+    /// A `RESOURCEHEADER` and its data
+    fn res_entry(resource_type: Vec<u8>, name: Vec<u8>, language: u16, data: &[u8]) -> Vec<u8> {
+        let mut ids = resource_type;
+        ids.extend(name);
+        pad4(&mut ids);
+
+        let mut ret = le32(data.len() as u32);
+        ret.extend(le32(8 + ids.len() as u32 + 16));
+        ret.extend(ids);
+        ret.extend(le32(0)); // DataVersion
+        ret.extend_from_slice(&[0x30, 0x10, language as u8, (language >> 8) as u8]); // MemoryFlags, LanguageId
+        ret.extend(le32(0)); // Version
+        ret.extend(le32(0)); // Characteristics
+        ret.extend_from_slice(data);
+        pad4(&mut ret);
+        ret
+    }
+
+    /// The empty resource `.res` files start with
+    fn empty() -> Vec<u8> {
+        res_entry(ordinal(0), ordinal(0), 0, b"")
+    }
+
+    fn res() -> Vec<u8> {
+        let mut ret = empty();
+        ret.extend(res_entry(ordinal(24), ordinal(1), 0x0409, b"<assembly/>"));
+        ret.extend(res_entry(name("CONFIG"), name("DEFAULTS"), 0x0000, b"{}"));
+        ret
+    }
+
+    #[test]
+    fn res_ids() {
+        assert_eq!(parse_res(&res()),
+                   Some(vec![CompiledResource {
+                                 resource_type: ResourceId::RT_MANIFEST,
+                                 name: ResourceId::Ordinal(1),
+                                 language: 0x0409,
+                             },
+                             CompiledResource {
+                                 resource_type: ResourceId::Name("CONFIG".to_string()),
+                                 name: ResourceId::Name("DEFAULTS".to_string()),
+                                 language: 0x0000,
+                             }]));
+        assert!(res().starts_with(RES_SIGNATURE));
+        assert_eq!(parse_res(&empty()), Some(vec![]));
+    }
+
+    #[test]
+    fn res_truncated() {
+        let res = res();
+        let first_end = empty().len() + res_entry(ordinal(24), ordinal(1), 0x0409, b"<assembly/>").len();
+        // The last resource's padding is optional: the first's data ends a byte before its end, the second's two bytes
+        for len in 0..res.len() - 2 {
+            let expected = match len {
+                0 => Some(0),
+                _ if len == empty().len() => Some(0),
+                _ if len == first_end - 1 || len == first_end => Some(1),
+                _ => None,
+            };
+            assert_eq!(parse_res(&res[..len]).map(|r| r.len()), expected, "{} bytes", len);
+        }
+        assert_eq!(parse_res(&res[..res.len() - 2]).map(|r| r.len()), Some(2));
+
+        let mut unterminated = empty();
+        unterminated.extend(le32(0));
+        unterminated.extend(le32(8 + 4));
+        unterminated.extend(utf16("NAME"));
+        assert_eq!(parse_res(&unterminated), None);
+    }
+
+    /// An AMD64 object with `sections` of the specified names, the last one holding `rsrc`
+    fn coff(sections: &[&[u8; 8]], rsrc: &[u8]) -> Vec<u8> {
+        let data_start = 20 + 40 * sections.len() as u32;
+
+        let mut ret = vec![0x64, 0x86, sections.len() as u8, 0];
+        ret.extend(le32(0)); // TimeDateStamp
+        ret.extend(le32(0)); // PointerToSymbolTable
+        ret.extend(le32(0)); // NumberOfSymbols
+        ret.extend_from_slice(&[0, 0, 0, 0]); // SizeOfOptionalHeader, Characteristics
+        for (i, section) in sections.iter().enumerate() {
+            let last = i == sections.len() - 1;
+            ret.extend_from_slice(&section[..]);
+            ret.extend(le32(0)); // VirtualSize
+            ret.extend(le32(0)); // VirtualAddress
+            ret.extend(le32(if last { rsrc.len() as u32 } else { 0 }));
+            ret.extend(le32(if last { data_start } else { 0 }));
+            ret.extend(vec![0; 16]);
+        }
+        ret.extend_from_slice(rsrc);
+        ret
+    }
+
+    /// An `IMAGE_RESOURCE_DIRECTORY` with `named` and `id` entries, of IDs or name offsets and `OffsetToData`s
+    fn directory(named: &[(u32, u32)], id: &[(u32, u32)]) -> Vec<u8> {
+        let mut ret = vec![0; 12];
+        ret.extend_from_slice(&[named.len() as u8, 0, id.len() as u8, 0]);
+        for &(name, offset_to_data) in named.iter().chain(id) {
+            ret.extend(le32(name));
+            ret.extend(le32(offset_to_data));
+        }
+        ret
+    }
+
+    fn rsrc() -> Vec<u8> {
+        let mut ret = directory(&[], &[(24, 0x8000_0000 | 24)]);
+        ret.extend(directory(&[(0x8000_0000 | 72, 0x8000_0000 | 48)], &[]));
+        ret.extend(directory(&[], &[(0x0409, 80)]));
+        ret.extend_from_slice(&[3, 0]);
+        ret.extend(utf16("APP"));
+        ret.extend(vec![0; 16]); // IMAGE_RESOURCE_DATA_ENTRY
+        ret
+    }
+
+    #[test]
+    fn coff_rsrc() {
+        let expected = vec![CompiledResource {
+                                resource_type: ResourceId::RT_MANIFEST,
+                                name: ResourceId::Name("APP".to_string()),
+                                language: 0x0409,
+                            }];
+        assert_eq!(parse_coff(&coff(&[b".rsrc$01"], &rsrc())), Some(expected.clone()));
+        assert_eq!(parse_coff(&coff(&[b".text\0\0\0", b".rsrc\0\0\0"], &rsrc())), Some(expected));
+        assert_eq!(parse_coff(&coff(&[b".text\0\0\0"], &rsrc())), None);
+    }
+
+    #[test]
+    fn coff_truncated() {
+        let object = coff(&[b".rsrc$01"], &rsrc());
+        for len in 0..object.len() - 16 {
+            assert_eq!(parse_coff(&object[..len]), None, "{} bytes", len);
+        }
+
+        let mut rsrc = rsrc();
+        rsrc[72] = 0xFF;
+        assert_eq!(parse_coff(&coff(&[b".rsrc$01"], &rsrc)), None);
+    }
+}
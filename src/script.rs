@@ -0,0 +1,489 @@
+use std::path::{PathBuf, Path};
+use super::ResourceId;
+use std::{fs, io, str};
+
+
+/// A resource declared in a resource script, as found by [`parse_resource_script()`](fn.parse_resource_script.html).
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ResourceEntry {
+    /// The type, with keywords like `ICON` resolved to the type they produce (`RT_GROUP_ICON` for `ICON`)
+    pub resource_type: ResourceId,
+    /// The name as written, or `None` for `STRINGTABLE`s, whose names are derived from the string IDs
+    pub name: Option<ResourceId>,
+    /// The 1-based line the declaration starts on
+    pub line: usize,
+}
+
+
+/// Read the resource script at the specified path and list the resources it declares.
+///
+/// UTF-16LE scripts are recognised by their BOM, as `RC.EXE` does; anything else is read as UTF-8.
+/// See [`parse_resource_script()`](fn.parse_resource_script.html) for the limitations.
+pub fn parse_resource_ids<P: AsRef<Path>>(rc: P) -> io::Result<Vec<ResourceEntry>> {
+    // Resource scripts are frequently in the ANSI codepage, which only matters for strings, which are skipped anyway
+    Ok(parse_resource_script(&decode(&fs::read(rc)?)))
+}
+
+/// List the resources declared in the specified resource script.
+///
+/// This is a lightweight scan of top-level declarations, not a full parser:
+/// comments and line continuations are handled, but preprocessor directives are skipped, not evaluated,
+/// so symbolic names are returned as written (except for types from `windows.h`, like `RT_MANIFEST`), declarations in `#include`d files aren't found,
+/// and declarations in `#if`ed-out sections are.
+///
+/// # Examples
+///
+/// ```rust
+/// # use embed_resource::{ResourceEntry, ResourceId, parse_resource_script};
+/// let entries = parse_resource_script(r#"
+/// #include "resource.h"
+///
+/// 1 ICON "app.ico" // The application icon
+/// IDD_ABOUT DIALOGEX 0, 0, 200, 100
+/// CAPTION "About"
+/// FONT 8, "MS Shell Dlg"
+/// BEGIN
+///     DEFPUSHBUTTON "OK", IDOK, 75, 80, 50, 14
+/// END
+/// /* 2 ICON "old.ico" */
+/// STRINGTABLE
+/// {
+///     1, "A string"
+/// }
+/// 1 24 \
+///     "app.manifest"
+/// "#);
+///
+/// assert_eq!(entries, vec![
+///     ResourceEntry { resource_type: ResourceId::RT_GROUP_ICON, name: Some(ResourceId::Ordinal(1)), line: 4 },
+///     ResourceEntry { resource_type: ResourceId::RT_DIALOG, name: Some(ResourceId::Name("IDD_ABOUT".to_string())), line: 5 },
+///     ResourceEntry { resource_type: ResourceId::RT_STRING, name: None, line: 12 },
+///     ResourceEntry { resource_type: ResourceId::RT_MANIFEST, name: Some(ResourceId::Ordinal(1)), line: 16 },
+/// ]);
+/// ```
+pub fn parse_resource_script(script: &str) -> Vec<ResourceEntry> {
+    scan(script).entries
+}
+
+/// Find the files the resource script at the specified path depends on:
+/// those `#include "d"`ed, recursively, and those resources are read from,
+/// that exist relative to the including file, the working directory, or one of the specified include directories.
+///
+/// `#include <d>`s are skipped, since they're system headers from `%INCLUDE%`.
+pub(crate) fn dependencies(rc: &Path, include_dirs: &[&PathBuf]) -> Vec<PathBuf> {
+    let mut ret = vec![];
+    collect_dependencies(rc, include_dirs, &mut ret);
+    ret
+}
+
+/// Like [`dependencies()`](fn.dependencies.html), but `None` if the script or any of the files it `#include`s couldn't be read,
+/// or isn't valid UTF-8 or UTF-16LE (with a BOM), like ANSI codepage scripts with non-ASCII strings, so that some `#include`s may have been missed.
+pub(crate) fn complete_dependencies(rc: &Path, include_dirs: &[&PathBuf]) -> Option<Vec<PathBuf>> {
+    let mut ret = vec![];
+    if collect_dependencies(rc, include_dirs, &mut ret) {
+        Some(ret)
+    } else {
+        None
+    }
+}
+
+/// Copy the resource script at the specified path to the specified one,
+/// blanking out the statements declaring resources of the types `keep()` rejects, so that line numbers stay the same.
+///
+/// Preprocessor directives in them are kept, so as not to unbalance `#if`s.
+/// UTF-16LE scripts (with a BOM) stay UTF-16LE; anything else is copied byte-for-byte, in whatever codepage it's in.
+pub(crate) fn filter_resources<F: Fn(&ResourceId) -> bool>(rc: &Path, into: &Path, keep: F) -> io::Result<()> {
+    let data = fs::read(rc)?;
+    let utf16 = is_utf16(&data);
+    let script = decode(&data);
+
+    let scan = scan(&script);
+    let blanked: Vec<_> = scan.entries.iter().zip(&scan.spans).filter(|&(e, _)| !keep(&e.resource_type)).map(|(_, &span)| span).collect();
+    let is_blanked = |line: usize| blanked.iter().any(|&(first, last)| first <= line && line <= last);
+
+    let filtered = if utf16 {
+        let mut ret = vec![0xFF, 0xFE];
+        for (i, line) in script.split('\n').enumerate() {
+            if i != 0 {
+                ret.extend_from_slice(&[b'\n', 0]);
+            }
+            if !is_blanked(i + 1) || line.trim_start().starts_with('#') {
+                ret.extend(line.encode_utf16().flat_map(|c| c.to_le_bytes().to_vec()));
+            }
+        }
+        ret
+    } else {
+        let mut ret = vec![];
+        for (i, line) in data.split(|&b| b == b'\n').enumerate() {
+            if i != 0 {
+                ret.push(b'\n');
+            }
+            if !is_blanked(i + 1) || String::from_utf8_lossy(line).trim_start().starts_with('#') {
+                ret.extend_from_slice(line);
+            }
+        }
+        ret
+    };
+    fs::write(into, filtered)
+}
+
+/// Whether the resource script is in UTF-16LE, which `RC.EXE` only recognises with a BOM
+fn is_utf16(data: &[u8]) -> bool {
+    data.starts_with(&[0xFF, 0xFE])
+}
+
+/// Whether the resource script decodes without replacement characters
+fn is_decodable(data: &[u8]) -> bool {
+    if is_utf16(data) {
+        data.len() % 2 == 0 && String::from_utf16(&data[2..].chunks(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect::<Vec<_>>()).is_ok()
+    } else {
+        str::from_utf8(data).is_ok()
+    }
+}
+
+/// The resource script's text, without the BOM if it's UTF-16LE, and lossily decoded as UTF-8 otherwise
+fn decode(data: &[u8]) -> String {
+    if is_utf16(data) {
+        String::from_utf16_lossy(&data[2..].chunks(2).map(|c| u16::from_le_bytes([c[0], *c.get(1).unwrap_or(&0)])).collect::<Vec<_>>())
+    } else {
+        String::from_utf8_lossy(data).into_owned()
+    }
+}
+
+
+/// Append the line to the resource script, in UTF-16LE if the script is (with a BOM), as-is otherwise.
+pub(crate) fn append_line(rc: &Path, line: &str) -> io::Result<()> {
+    let mut data = fs::read(rc)?;
+    let line = format!("\n{}\n", line);
+    if is_utf16(&data) {
+        data.extend(line.encode_utf16().flat_map(|c| c.to_le_bytes().to_vec()));
+    } else {
+        data.extend_from_slice(line.as_bytes());
+    }
+    fs::write(rc, data)
+}
+
+
+struct Scan {
+    entries: Vec<ResourceEntry>,
+    /// The first and last line of each entry's statement
+    spans: Vec<(usize, usize)>,
+    includes: Vec<String>,
+    files: Vec<String>,
+}
+
+fn scan(script: &str) -> Scan {
+    let mut ret = Scan {
+        entries: vec![],
+        spans: vec![],
+        includes: vec![],
+        files: vec![],
+    };
+    let mut depth = 0usize;
+    // After a declaration whose BEGIN hasn't been seen yet, top-level lines are its optional statements
+    let mut awaiting_block = false;
+
+    for (line, end_line, tokens) in logical_lines(script) {
+        if tokens.first().map(|t| !t.quoted && t.text.starts_with('#')).unwrap_or(true) {
+            if tokens[0].text == "#include" && tokens.len() >= 2 && tokens[1].quoted {
+                ret.includes.push(tokens[1].text.clone());
+            }
+            continue;
+        }
+
+        let mut body = &tokens[..];
+        let in_statement = depth != 0 || awaiting_block;
+        if !in_statement {
+            let first = &tokens[0];
+            if !first.quoted && first.text.eq_ignore_ascii_case("STRINGTABLE") {
+                ret.entries.push(ResourceEntry {
+                    resource_type: ResourceId::RT_STRING,
+                    name: None,
+                    line,
+                });
+                awaiting_block = true;
+                body = &tokens[1..];
+            } else if !first.quoted && ["LANGUAGE", "VERSION", "CHARACTERISTICS"].iter().any(|k| first.text.eq_ignore_ascii_case(k)) {
+                continue;
+            } else if tokens.len() >= 2 {
+                let (resource_type, always_block) = resource_type(&tokens[1]);
+                ret.entries.push(ResourceEntry {
+                    resource_type,
+                    name: Some(resource_name(first)),
+                    line,
+                });
+
+                body = &tokens[2..];
+                // Otherwise the resource is read from a file
+                awaiting_block = always_block || body.iter().all(|t| !t.quoted && (is_block_delimiter(&t.text) || is_memory_flag(&t.text)));
+                if !awaiting_block {
+                    if let Some(file) = body.last() {
+                        ret.files.push(file.text.replace("\\\\", "\\"));
+                    }
+                }
+            }
+        }
+
+        for token in body.iter().filter(|t| !t.quoted) {
+            if token.text == "{" || token.text.eq_ignore_ascii_case("BEGIN") {
+                depth += 1;
+                awaiting_block = false;
+            } else if token.text == "}" || token.text.eq_ignore_ascii_case("END") {
+                depth = depth.saturating_sub(1);
+            }
+        }
+
+        if ret.spans.len() < ret.entries.len() {
+            ret.spans.push((line, end_line));
+        } else if in_statement {
+            if let Some(span) = ret.spans.last_mut() {
+                span.1 = end_line;
+            }
+        }
+    }
+
+    ret
+}
+
+/// Whether all the files scanned could be read and decoded
+fn collect_dependencies(file: &Path, include_dirs: &[&PathBuf], into: &mut Vec<PathBuf>) -> bool {
+    let data = match fs::read(file) {
+        Ok(data) => data,
+        Err(_) => return false,
+    };
+    let mut complete = is_decodable(&data);
+    let scan = scan(&decode(&data));
+    let dir = file.parent().unwrap_or_else(|| Path::new(""));
+
+    let resolve = |name: &str| {
+        [dir.join(name), PathBuf::from(name)]
+            .iter()
+            .cloned()
+            .chain(include_dirs.iter().map(|d| d.join(name)))
+            .find(|p| p.is_file())
+    };
+    for include in scan.includes.iter().filter_map(|i| resolve(i)) {
+        if !into.contains(&include) {
+            into.push(include.clone());
+            complete &= collect_dependencies(&include, include_dirs, into);
+        }
+    }
+    for resource in scan.files.iter().filter_map(|f| resolve(f)) {
+        if !into.contains(&resource) {
+            into.push(resource);
+        }
+    }
+    complete
+}
+
+
+struct Token {
+    text: String,
+    quoted: bool,
+}
+
+/// Split the script into lines of tokens, with the first and last physical line numbers,
+/// joining continued lines, and dropping comments and commas
+fn logical_lines(script: &str) -> Vec<(usize, usize, Vec<Token>)> {
+    let mut ret = vec![];
+    let mut tokens = vec![];
+    let mut start_line = 1;
+    let mut cur_line = 1;
+
+    let mut chars = script.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().cloned();
+        match c {
+            '\n' => {
+                if !tokens.is_empty() {
+                    ret.push((start_line, cur_line, tokens));
+                    tokens = vec![];
+                }
+                cur_line += 1;
+                start_line = cur_line;
+            }
+            '\\' if next == Some('\n') || next == Some('\r') => {
+                if chars.next() == Some('\r') && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                cur_line += 1;
+            }
+            '/' if next == Some('/') => {
+                while chars.peek().map(|&c| c != '\n').unwrap_or(false) {
+                    chars.next();
+                }
+            }
+            '/' if next == Some('*') => {
+                chars.next();
+                let mut prev = '\0';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        cur_line += 1;
+                    }
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            '"' => {
+                let mut text = String::new();
+                while let Some(c) = chars.next() {
+                    let next = chars.peek().cloned();
+                    match c {
+                        '"' if next == Some('"') => {
+                            chars.next();
+                            text.push('"');
+                        }
+                        '"' | '\n' => break,
+                        c => text.push(c),
+                    }
+                }
+                tokens.push(Token {
+                    text,
+                    quoted: true,
+                });
+            }
+            c if c.is_whitespace() || c == ',' => {}
+            c => {
+                let mut text = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == ',' || c == '"' {
+                        break;
+                    }
+                    text.push(c);
+                    chars.next();
+                }
+                tokens.push(Token {
+                    text,
+                    quoted: false,
+                });
+            }
+        }
+    }
+    if !tokens.is_empty() {
+        ret.push((start_line, cur_line, tokens));
+    }
+
+    ret
+}
+
+/// The resource type produced by the type token, and whether it's always followed by a block
+fn resource_type(token: &Token) -> (ResourceId, bool) {
+    if token.quoted {
+        return (ResourceId::Name(token.text.clone()), false);
+    }
+
+    let keyword = match &token.text.to_ascii_uppercase()[..] {
+        "CURSOR" => Some((ResourceId::RT_GROUP_CURSOR, false)),
+        "BITMAP" => Some((ResourceId::RT_BITMAP, false)),
+        "ICON" => Some((ResourceId::RT_GROUP_ICON, false)),
+        "MENU" | "MENUEX" => Some((ResourceId::RT_MENU, true)),
+        "DIALOG" | "DIALOGEX" => Some((ResourceId::RT_DIALOG, true)),
+        "FONT" => Some((ResourceId::RT_FONT, false)),
+        "ACCELERATORS" => Some((ResourceId::RT_ACCELERATOR, true)),
+        "RCDATA" => Some((ResourceId::RT_RCDATA, false)),
+        "MESSAGETABLE" => Some((ResourceId::RT_MESSAGETABLE, false)),
+        "VERSIONINFO" => Some((ResourceId::RT_VERSION, true)),
+        "DLGINCLUDE" => Some((ResourceId::RT_DLGINCLUDE, false)),
+        "PLUGPLAY" => Some((ResourceId::RT_PLUGPLAY, false)),
+        "VXD" => Some((ResourceId::RT_VXD, false)),
+        "ANICURSOR" => Some((ResourceId::RT_ANICURSOR, false)),
+        "ANIICON" => Some((ResourceId::RT_ANIICON, false)),
+        "HTML" => Some((ResourceId::RT_HTML, false)),
+        // From winuser.h, which is frequently #included for them
+        "RT_CURSOR" => Some((ResourceId::RT_CURSOR, false)),
+        "RT_BITMAP" => Some((ResourceId::RT_BITMAP, false)),
+        "RT_ICON" => Some((ResourceId::RT_ICON, false)),
+        "RT_RCDATA" => Some((ResourceId::RT_RCDATA, false)),
+        "RT_MESSAGETABLE" => Some((ResourceId::RT_MESSAGETABLE, false)),
+        "RT_GROUP_CURSOR" => Some((ResourceId::RT_GROUP_CURSOR, false)),
+        "RT_GROUP_ICON" => Some((ResourceId::RT_GROUP_ICON, false)),
+        "RT_HTML" => Some((ResourceId::RT_HTML, false)),
+        "RT_MANIFEST" => Some((ResourceId::RT_MANIFEST, false)),
+        _ => None,
+    };
+    keyword.unwrap_or_else(|| (resource_name(token), false))
+}
+
+fn resource_name(token: &Token) -> ResourceId {
+    if token.quoted {
+        return ResourceId::Name(token.text.clone());
+    }
+
+    let number = token.text.trim_end_matches(&['L', 'l'][..]);
+    let ordinal = if number.starts_with("0x") || number.starts_with("0X") {
+        u16::from_str_radix(&number[2..], 16).ok()
+    } else {
+        number.parse().ok()
+    };
+    match ordinal {
+        Some(o) => ResourceId::Ordinal(o),
+        None => ResourceId::Name(token.text.clone()),
+    }
+}
+
+fn is_block_delimiter(token: &str) -> bool {
+    token == "{" || token.eq_ignore_ascii_case("BEGIN")
+}
+
+fn is_memory_flag(token: &str) -> bool {
+    ["PRELOAD", "LOADONCALL", "FIXED", "MOVEABLE", "DISCARDABLE", "PURE", "IMPURE", "SHARED", "NONSHARED"].iter().any(|f| token.eq_ignore_ascii_case(f))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::test_dir;
+    use super::{ResourceEntry, complete_dependencies, dependencies, parse_resource_ids};
+    use super::super::ResourceId;
+    use std::fs;
+
+    fn utf16(text: &str) -> Vec<u8> {
+        let mut ret = vec![0xFF, 0xFE];
+        ret.extend(text.encode_utf16().flat_map(|c| c.to_le_bytes().to_vec()));
+        ret
+    }
+
+    #[test]
+    fn dependencies_of_utf16_script() {
+        let dir = test_dir("utf16-dependencies");
+        fs::write(dir.join("app.rc"), utf16("#include \"resource.h\"\r\n1 ICON \"app.ico\"\r\n")).unwrap();
+        fs::write(dir.join("resource.h"), utf16("#include \"version.h\"\r\n")).unwrap();
+        fs::write(dir.join("version.h"), "#define VERSION 1\n").unwrap();
+        fs::write(dir.join("app.ico"), b"").unwrap();
+
+        assert_eq!(dependencies(&dir.join("app.rc"), &[]), vec![dir.join("resource.h"), dir.join("version.h"), dir.join("app.ico")]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn complete_dependencies_need_decodable_includes() {
+        let dir = test_dir("complete-dependencies");
+        fs::write(dir.join("app.rc"), "#include \"strings.rc\"\n1 ICON \"app.ico\"\n").unwrap();
+        fs::write(dir.join("strings.rc"), utf16("STRINGTABLE { 1, \"\u{e9}t\u{e9}\" }\r\n")).unwrap();
+        fs::write(dir.join("app.ico"), b"").unwrap();
+        assert_eq!(complete_dependencies(&dir.join("app.rc"), &[]), Some(vec![dir.join("strings.rc"), dir.join("app.ico")]));
+
+        // "été" in Windows-1252
+        fs::write(dir.join("strings.rc"), &b"STRINGTABLE { 1, \"\xE9t\xE9\" }\n"[..]).unwrap();
+        assert_eq!(complete_dependencies(&dir.join("app.rc"), &[]), None);
+        assert_eq!(dependencies(&dir.join("app.rc"), &[]), vec![dir.join("strings.rc"), dir.join("app.ico")]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resource_ids_of_utf16_script() {
+        let dir = test_dir("utf16-resource-ids");
+        fs::write(dir.join("app.rc"), utf16("// \u{e9}t\u{e9}\r\n1 ICON \"app.ico\"\r\nIDD_ABOUT DIALOGEX 0, 0, 200, 100\r\nBEGIN\r\nEND\r\n")).unwrap();
+
+        assert_eq!(parse_resource_ids(dir.join("app.rc")).unwrap(), vec![
+            ResourceEntry { resource_type: ResourceId::RT_GROUP_ICON, name: Some(ResourceId::Ordinal(1)), line: 2 },
+            ResourceEntry { resource_type: ResourceId::RT_DIALOG, name: Some(ResourceId::Name("IDD_ABOUT".to_string())), line: 3 },
+        ]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,315 @@
+//! What the backends share: the SDK layout logic, which is only used on Windows with MSVC, but compiled for tests everywhere,
+//! the version sorting, also used for cross-compilation SDKs, and the compiler arguments,
+//! since `llvm-rc` and `llvm-windres` take `RC.EXE`'s and `windres`' switches, respectively.
+
+use std::ffi::OsString;
+#[cfg(any(test, not(target_os = "windows"), target_env = "msvc"))]
+use std::ffi::OsStr;
+#[cfg(any(test, not(target_os = "windows"), target_env = "msvc"))]
+use std::cmp::Reverse;
+use super::{Options, Symbol};
+#[cfg(any(test, all(target_os = "windows", target_env = "msvc")))]
+use std::sync::atomic::AtomicPtr;
+#[cfg(any(test, all(target_os = "windows", target_env = "msvc")))]
+use std::io::ErrorKind as IoErrorKind;
+#[cfg(any(test, all(target_os = "windows", target_env = "msvc")))]
+use std::sync::Mutex;
+#[cfg(any(test, not(target_os = "windows"), target_env = "msvc"))]
+use std::path::PathBuf;
+#[cfg(any(test, all(target_os = "windows", target_env = "msvc")))]
+use std::path::Path;
+#[cfg(any(test, all(target_os = "windows", target_env = "msvc")))]
+use std::{fs, ptr};
+#[cfg(any(test, all(target_os = "windows", target_env = "msvc")))]
+use super::{Arch, debug_log, lazy};
+
+
+/// `10.0.19041.0` -> `[10, 0, 19041, 0]`
+#[cfg(any(test, not(target_os = "windows"), target_env = "msvc"))]
+pub fn parse_version(name: &OsStr) -> Option<Vec<u32>> {
+    name.to_str()?.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Sort the directory names newest version first, then anything that isn't a version, in name order
+#[cfg(any(test, not(target_os = "windows"), target_env = "msvc"))]
+pub fn sort_newest_first(versions: &mut [OsString]) {
+    versions.sort();
+    versions.sort_by_key(|v| Reverse(parse_version(v)));
+}
+
+
+/// A kits root, the architecture, and its bin dirs
+#[cfg(any(test, all(target_os = "windows", target_env = "msvc")))]
+type BinDirs = (PathBuf, Arch, Vec<PathBuf>);
+
+/// All \bin\<version>\<arch>\ folders, newest version first, listed only once per process,
+/// since build machines can have dozens of SDK versions installed, and every tool lookup goes through here.
+///
+/// This is still the layout of the Windows 11 SDKs installed with Visual Studio 2022 (like \bin\10.0.22621.0\x64\rc.exe,
+/// next to \arm64\ and \x86\); the Spectre-mitigated \spectre\ subfolders are only under MSVC's \lib\, not the SDK's \bin\.
+/// Versions whose folder lacks the tool, like ones left behind by uninstalled SDKs, are skipped by the caller
+#[cfg(any(test, all(target_os = "windows", target_env = "msvc")))]
+pub fn windows_10_kits_bin_dirs(root_dir: &Path, arch: Arch) -> Vec<PathBuf> {
+    static BIN_DIRS: AtomicPtr<Mutex<Vec<BinDirs>>> = AtomicPtr::new(ptr::null_mut());
+
+    let mut bin_dirs = lazy(&BIN_DIRS, || Mutex::new(vec![])).lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, _, dirs)) = bin_dirs.iter().find(|(r, a, _)| r == root_dir && *a == arch) {
+        return dirs.clone();
+    }
+
+    let mut versions: Vec<OsString> = match read_dir_logged(root_dir) {
+        Some(rd) => rd.flatten().filter(|entry| entry.file_type().map(|ft| !ft.is_file()).unwrap_or(false)).map(|entry| entry.file_name()).collect(),
+        None => vec![],
+    };
+    // Anything that isn't a version, like the pre-10.0.15063 x86\ and x64\, goes last
+    sort_newest_first(&mut versions);
+
+    let dirs: Vec<_> = versions.into_iter().filter_map(|v| try_bin_dir(root_dir.join(v), "x86", "x64", arch)).collect();
+    bin_dirs.push((root_dir.to_path_buf(), arch, dirs.clone()));
+    dirs
+}
+
+#[cfg(any(test, all(target_os = "windows", target_env = "msvc")))]
+pub fn try_bin_dir<R: Into<PathBuf>>(root_dir: R, x86_bin: &str, x64_bin: &str, arch: Arch) -> Option<PathBuf> {
+    try_bin_dir_impl(root_dir.into(), x86_bin, x64_bin, arch)
+}
+
+#[cfg(any(test, all(target_os = "windows", target_env = "msvc")))]
+fn try_bin_dir_impl(root_dir: PathBuf, x86_bin: &str, x64_bin: &str, arch: Arch) -> Option<PathBuf> {
+    let bins = match arch {
+        Arch::X86 => vec![x86_bin.to_string()],
+        Arch::X64 => vec![x64_bin.to_string()],
+        // The resource compiler's output is architecture-independent, so use the host's own tools if there are any,
+        // and otherwise the x86 ones, since Windows 10 on ARM only emulates x86, and x64 only from Windows 11
+        Arch::Arm | Arch::Arm64 if cfg!(target_arch = "aarch64") => vec![x64_bin.replace("x64", "arm64"), x86_bin.to_string()],
+        Arch::Arm | Arch::Arm64 => vec![x86_bin.to_string()],
+    };
+
+    bins.into_iter().map(|bin| root_dir.join(bin)).find(|dir| dir.is_dir())
+}
+
+/// Permission errors on locked-down machines would otherwise be indistinguishable from the directory not existing
+#[cfg(any(test, all(target_os = "windows", target_env = "msvc")))]
+pub fn read_dir_logged(dir: &Path) -> Option<fs::ReadDir> {
+    match fs::read_dir(dir) {
+        Ok(rd) => Some(rd),
+        Err(ref e) if e.kind() == IoErrorKind::PermissionDenied => {
+            debug_log(format_args!("permission denied listing {}, skipping", dir.display()));
+            None
+        }
+        Err(ref e) if e.kind() == IoErrorKind::NotFound => None,
+        Err(e) => {
+            debug_log(format_args!("couldn't list {}: {}, skipping", dir.display(), e));
+            None
+        }
+    }
+}
+
+/// Windows paths are case-insensitive, and the registry values may or may not end with a path separator
+#[cfg(any(test, all(target_os = "windows", target_env = "msvc")))]
+pub fn is_same_dir(lhs: &Path, rhs: &Path) -> bool {
+    let normalise = |p: &Path| p.to_string_lossy().trim_end_matches(&['\\', '/'][..]).replace('/', "\\").to_lowercase();
+    normalise(lhs) == normalise(rhs)
+}
+
+/// The directories, in order, without the later ones that are the [same](fn.is_same_dir.html) as an earlier one,
+/// like when several `KitsRoot*` registry values point to the same kit
+#[cfg(any(test, all(target_os = "windows", target_env = "msvc")))]
+pub fn dedup_dirs<I: IntoIterator<Item = PathBuf>>(dirs: I) -> Vec<PathBuf> {
+    let mut ret: Vec<PathBuf> = vec![];
+    for dir in dirs {
+        if !ret.iter().any(|r| is_same_dir(r, &dir)) {
+            ret.push(dir);
+        }
+    }
+    ret
+}
+
+
+/// `RC.EXE`'s switches, also taken by `llvm-rc`, with the codepage defaulting to the specified one,
+/// and the compiler's own include directories searched after the user's
+#[cfg(any(test, not(target_os = "windows"), target_env = "msvc"))]
+pub fn rc_arguments<I: IntoIterator<Item = PathBuf>>(resource: &str, out_file: &str, default_codepage: Option<u16>, include_dirs: I, options: &Options)
+                                                     -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["/fo".into(), out_file.into()];
+    if options.verbose.unwrap_or(false) {
+        args.push("/v".into());
+    }
+    if options.null_terminate_strings.unwrap_or(false) {
+        args.push("/n".into());
+    }
+    for symbol in &options.symbols {
+        let flag = match *symbol {
+            Symbol::Define(..) => "/d",
+            Symbol::Undefine(_) => "/u",
+        };
+        args.push(flag.into());
+        args.push(symbol.argument().into());
+    }
+    if let Some(codepage) = options.codepage.or(default_codepage) {
+        args.push("/c".into());
+        args.push(codepage.to_string().into());
+    }
+    if let Some(langid) = options.language {
+        args.push("/l".into());
+        args.push(format!("0x{:x}", langid).into());
+    }
+    for dir in options.search_dirs().cloned().chain(include_dirs) {
+        args.push("/i".into());
+        args.push(dir.into());
+    }
+    args.push(resource.into());
+    args
+}
+
+/// `windres`' switches, also taken by `llvm-windres`
+#[cfg(any(test, not(all(target_os = "windows", target_env = "msvc"))))]
+pub fn windres_arguments(resource: &str, output_format: &str, out_file: &str, options: &Options) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["--input".into(), resource.into(), output_format.into(), "--output".into(), out_file.into()];
+    if options.verbose.unwrap_or(false) {
+        args.push("--verbose".into());
+    }
+    for symbol in &options.symbols {
+        let flag = match *symbol {
+            Symbol::Define(..) => "-D",
+            Symbol::Undefine(_) => "-U",
+        };
+        args.push(flag.into());
+        args.push(symbol.argument().into());
+    }
+    if let Some(codepage) = options.codepage {
+        args.push(format!("--codepage={}", codepage).into());
+    }
+    if let Some(langid) = options.language {
+        args.push(format!("--language=0x{:x}", langid).into());
+    }
+    for dir in options.search_dirs() {
+        args.push("--include-dir".into());
+        args.push(dir.into());
+    }
+    args
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_version, sort_newest_first, windows_10_kits_bin_dirs, try_bin_dir, is_same_dir, dedup_dirs, rc_arguments, windres_arguments};
+    use super::super::Options;
+    use std::path::{PathBuf, Path};
+    use std::ffi::{OsString, OsStr};
+    use super::super::tests::test_dir;
+    use super::super::Arch;
+    use std::fs;
+
+    #[test]
+    fn parse_version_numbers() {
+        assert_eq!(parse_version(OsStr::new("10.0.19041.0")), Some(vec![10, 0, 19041, 0]));
+        assert_eq!(parse_version(OsStr::new("x64")), None);
+        assert_eq!(parse_version(OsStr::new("10.0.x")), None);
+    }
+
+    #[test]
+    fn sort_newest_first_numerically() {
+        let mut versions: Vec<OsString> = vec!["10.0.9200.0", "x86", "10.0.10240.0", "10.0.22621.0", "x64", "10.0.19041.0"]
+            .into_iter()
+            .map(OsString::from)
+            .collect();
+        sort_newest_first(&mut versions);
+        assert_eq!(versions, ["10.0.22621.0", "10.0.19041.0", "10.0.10240.0", "10.0.9200.0", "x64", "x86"]);
+    }
+
+    #[test]
+    fn windows_10_kits_bin_dirs_many_versions() {
+        let bin = test_dir("many-versions").join("bin");
+        // 24 SDKs, of which every third was uninstalled, leaving its folder but no x64\
+        for build in 0..24 {
+            let version = bin.join(format!("10.0.{}.0", 10000 + build * 500));
+            fs::create_dir_all(if build % 3 == 0 { version } else { version.join("x64") }).unwrap();
+        }
+        fs::create_dir_all(bin.join("x64")).unwrap();
+
+        let dirs = windows_10_kits_bin_dirs(&bin, Arch::X64);
+        assert_eq!(dirs.len(), 16);
+        assert_eq!(dirs[0], bin.join("10.0.21500.0").join("x64"));
+        assert_eq!(dirs[1], bin.join("10.0.21000.0").join("x64"));
+        assert!(dirs.windows(2).all(|pair| parse_version(pair[0].parent().unwrap().file_name().unwrap()) >
+                                           parse_version(pair[1].parent().unwrap().file_name().unwrap())));
+        assert!(windows_10_kits_bin_dirs(&bin, Arch::X86).is_empty());
+
+        // Listed once: a newly installed version isn't seen by later lookups in the same process
+        fs::create_dir_all(bin.join("10.0.30000.0").join("x64")).unwrap();
+        assert_eq!(windows_10_kits_bin_dirs(&bin, Arch::X64), dirs);
+
+        fs::remove_dir_all(bin.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn arm_bin_dir() {
+        let root = test_dir("arm-bin-dir");
+        for arch in &["x86", "x64", "arm64"] {
+            fs::create_dir_all(root.join("bin").join(arch)).unwrap();
+        }
+
+        assert_eq!(try_bin_dir(&root, "bin/x86", "bin/x64", Arch::X64), Some(root.join("bin/x64")));
+        assert_eq!(try_bin_dir(&root, "bin/x86", "bin/x64", Arch::Arm64),
+                   Some(root.join(if cfg!(target_arch = "aarch64") { "bin/arm64" } else { "bin/x86" })));
+        fs::remove_dir(root.join("bin").join("arm64")).unwrap();
+        assert_eq!(try_bin_dir(&root, "bin/x86", "bin/x64", Arch::Arm), Some(root.join("bin/x86")));
+        fs::remove_dir(root.join("bin").join("x86")).unwrap();
+        assert_eq!(try_bin_dir(&root, "bin/x86", "bin/x64", Arch::Arm64), None);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn same_kits_root_under_two_keys() {
+        // KitsRoot10 and KitsRoot81 both pointing to the Windows 10 kit, as with some SDK installers
+        let roots = dedup_dirs(vec![PathBuf::from(r"C:\Program Files (x86)\Windows Kits\10\"),
+                                    PathBuf::from(r"c:\program files (x86)\windows kits\10"),
+                                    PathBuf::from(r"C:\Program Files (x86)\Windows Kits\8.1\"),
+                                    PathBuf::from("C:/Program Files (x86)/Windows Kits/10/")]);
+        assert_eq!(roots, [PathBuf::from(r"C:\Program Files (x86)\Windows Kits\10\"), PathBuf::from(r"C:\Program Files (x86)\Windows Kits\8.1\")]);
+
+        assert!(!is_same_dir(Path::new(r"C:\Windows Kits\10"), Path::new(r"C:\Windows Kits\10.0")));
+    }
+
+    #[test]
+    fn vs2022_windows_11_sdk_layout() {
+        let root = test_dir("vs2022-layout");
+        let kit = root.join("Windows Kits").join("10");
+        for version in &["10.0.19041.0", "10.0.22000.0", "10.0.22621.0"] {
+            for arch in &["x86", "x64", "arm64"] {
+                let bin = kit.join("bin").join(version).join(arch);
+                fs::create_dir_all(&bin).unwrap();
+                fs::write(bin.join("rc.exe"), b"").unwrap();
+            }
+        }
+        // The pre-10.0.15063 layout, which some tools are still installed in
+        fs::create_dir_all(kit.join("bin").join("x64")).unwrap();
+
+        let dirs = windows_10_kits_bin_dirs(&kit.join("bin"), Arch::X64);
+        assert_eq!(dirs, [kit.join("bin/10.0.22621.0/x64"), kit.join("bin/10.0.22000.0/x64"), kit.join("bin/10.0.19041.0/x64")]);
+        assert!(dirs[0].join("rc.exe").is_file());
+        assert_eq!(windows_10_kits_bin_dirs(&kit.join("bin"), Arch::X86)[0], kit.join("bin/10.0.22621.0/x86"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn rc_arguments_order() {
+        let options = Options::new().define("VERSION", Some("2")).undefine("DEBUG").language(0x0407).include_dir("res").verbose(true);
+        let args = rc_arguments("app.rc", "app.res", Some(65001), vec![PathBuf::from("sdk/um")], &options);
+        assert_eq!(args, ["/fo", "app.res", "/v", "/d", "VERSION=2", "/u", "DEBUG", "/c", "65001", "/l", "0x407", "/i", "res", "/i", "sdk/um", "app.rc"]);
+
+        let args = rc_arguments("app.rc", "app.res", None, vec![], &options.codepage(1252));
+        assert_eq!(&args[7..9], ["/c", "1252"]);
+        assert_eq!(rc_arguments("app.rc", "app.res", None, vec![], &Options::new()), ["/fo", "app.res", "app.rc"]);
+    }
+
+    #[test]
+    fn windres_arguments_order() {
+        let options = Options::new().define("VERSION", Some("2")).codepage(65001).include_dir("res");
+        assert_eq!(windres_arguments("app.rc", "--output-format=coff", "app.o", &options),
+                   ["--input", "app.rc", "--output-format=coff", "--output", "app.o", "-D", "VERSION=2", "--codepage=65001", "--include-dir", "res"]);
+    }
+}
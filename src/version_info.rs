@@ -0,0 +1,227 @@
+use std::process::Command;
+use std::env;
+
+
+/// Environment variable overriding the commit hash used by [`VersionInfo::git_commit()`](struct.VersionInfo.html#method.git_commit)
+pub const GIT_HASH_ENV_VAR: &str = "EMBED_RESOURCE_GIT_HASH";
+
+
+/// A `VERSIONINFO` resource, the version information shown in Explorer's file properties, built up and rendered to a resource script.
+///
+/// The strings are declared as U.S. English, Unicode (`040904b0`), unless another [language](#method.language) is specified.
+///
+/// # Examples
+///
+/// ```rust
+/// # use embed_resource::VersionInfo;
+/// let rc = VersionInfo::new()
+///     .file_version([1, 2, 3, 0])
+///     .product_version([1, 2, 0, 0])
+///     .string("ProductName", "Checksums")
+///     .to_rc();
+/// assert!(rc.contains("FILEVERSION 1,2,3,0\n"));
+/// assert!(rc.contains(r#"VALUE "ProductName", "Checksums""#));
+/// ```
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct VersionInfo {
+    file_version: [u16; 4],
+    product_version: [u16; 4],
+    prerelease: bool,
+    strings: Vec<(String, String)>,
+    pub(crate) language: Option<u16>,
+}
+
+impl VersionInfo {
+    /// Version information with all versions 0 and no strings.
+    pub fn new() -> VersionInfo {
+        VersionInfo::default()
+    }
+
+    /// Version information for the crate being built, from the `CARGO_PKG_*` environment variables cargo sets for build scripts.
+    ///
+    /// The versions are set from `CARGO_PKG_VERSION` like by [`version()`](#method.version),
+    /// and `ProductName` and, if the package has one, `FileDescription` are set.
+    pub fn from_cargo_env() -> VersionInfo {
+        let var = |name| env::var(name).unwrap_or_default();
+
+        let ret = VersionInfo::new().version(&var("CARGO_PKG_VERSION")).string("ProductName", var("CARGO_PKG_NAME"));
+        match var("CARGO_PKG_DESCRIPTION") {
+            ref description if description.is_empty() => ret,
+            description => ret.string("FileDescription", description),
+        }
+    }
+
+    /// Set the versions from a semantic version, like `1.2.3-beta.1+abcdef`.
+    ///
+    /// The binary file and product versions are `major.minor.patch.0`, with missing or malformed parts 0,
+    /// the `FileVersion` and `ProductVersion` strings are the whole version, build metadata included,
+    /// and the file is marked as a [prerelease](#method.prerelease) if it has a prerelease part.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use embed_resource::VersionInfo;
+    /// let rc = VersionInfo::new().version("1.2.3-beta.1+abcdef").to_rc();
+    /// assert!(rc.contains("FILEVERSION 1,2,3,0\n"));
+    /// assert!(rc.contains("FILEFLAGS 0x2L\n"));
+    /// assert!(rc.contains(r#"VALUE "ProductVersion", "1.2.3-beta.1+abcdef""#));
+    ///
+    /// let rc = VersionInfo::new().version("2.1").to_rc();
+    /// assert!(rc.contains("PRODUCTVERSION 2,1,0,0\n"));
+    /// assert!(rc.contains("FILEFLAGS 0x0L\n"));
+    /// ```
+    pub fn version(self, version: &str) -> VersionInfo {
+        let without_build = version.split('+').next().unwrap_or("");
+        let (core, prerelease) = match without_build.find('-') {
+            Some(dash) => (&without_build[..dash], true),
+            None => (without_build, false),
+        };
+        let mut numbers = [0; 4];
+        for (number, part) in numbers.iter_mut().zip(core.split('.').take(3)) {
+            *number = part.trim().parse().unwrap_or(0);
+        }
+
+        self.file_version(numbers)
+            .product_version(numbers)
+            .prerelease(prerelease)
+            .string("FileVersion", version)
+            .string("ProductVersion", version)
+    }
+
+    /// Set the binary file version (`FILEVERSION`), most significant part first.
+    pub fn file_version(mut self, version: [u16; 4]) -> VersionInfo {
+        self.file_version = version;
+        self
+    }
+
+    /// Set the binary product version (`PRODUCTVERSION`), most significant part first.
+    pub fn product_version(mut self, version: [u16; 4]) -> VersionInfo {
+        self.product_version = version;
+        self
+    }
+
+    /// Mark the file as a development version, not a commercially released product, with `VS_FF_PRERELEASE` in `FILEFLAGS`.
+    pub fn prerelease(mut self, prerelease: bool) -> VersionInfo {
+        self.prerelease = prerelease;
+        self
+    }
+
+    /// Declare the version information, and its strings, in the specified language, as a `LANGID` like `0x0407` for German (Germany),
+    /// instead of U.S. English (`0x0409`), for example for localized builds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use embed_resource::VersionInfo;
+    /// let rc = VersionInfo::new().language(0x0407).string("ProductName", "Prüfsummen").to_rc();
+    /// assert!(rc.contains("LANGUAGE 0x7, 0x1\n"));
+    /// assert!(rc.contains(r#"BLOCK "040704b0""#));
+    /// assert!(rc.contains(r#"VALUE "Translation", 0x407, 1200"#));
+    /// ```
+    pub fn language(mut self, langid: u16) -> VersionInfo {
+        self.language = Some(langid);
+        self
+    }
+
+    /// Set a `StringFileInfo` value, like `CompanyName` or `LegalCopyright`, replacing the previous value for the same key.
+    pub fn string<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> VersionInfo {
+        let key = key.into();
+        let value = value.into();
+        match self.strings.iter().position(|s| s.0 == key) {
+            Some(i) => self.strings[i].1 = value,
+            None => self.strings.push((key, value)),
+        }
+        self
+    }
+
+    /// Set the specified `StringFileInfo` value, like `Comments` or a custom `GitCommit`, to the commit being built.
+    ///
+    /// The hash is taken from `$EMBED_RESOURCE_GIT_HASH` if set, otherwise, if `run_git`, from `git rev-parse HEAD`;
+    /// if neither is available, or `git` fails, it's `unknown`.
+    ///
+    /// The hash is resolved immediately. With [rerun directives](struct.Options.html#method.rerun_directives) on,
+    /// compiling the version information emits `cargo:rerun-if-env-changed=EMBED_RESOURCE_GIT_HASH`,
+    /// like for the crate's other [environment variables](index.html#rerunning), but the build script isn't rerun for new commits,
+    /// so, with `run_git`, you may want to emit `cargo:rerun-if-changed=.git/HEAD`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use embed_resource::VersionInfo;
+    /// # use std::env;
+    /// env::set_var("EMBED_RESOURCE_GIT_HASH", "0123abcd");
+    /// assert!(VersionInfo::new().git_commit("GitCommit", false).to_rc().contains(r#"VALUE "GitCommit", "0123abcd""#));
+    ///
+    /// env::remove_var("EMBED_RESOURCE_GIT_HASH");
+    /// assert!(VersionInfo::new().git_commit("Comments", false).to_rc().contains(r#"VALUE "Comments", "unknown""#));
+    /// ```
+    pub fn git_commit<K: Into<String>>(self, key: K, run_git: bool) -> VersionInfo {
+        self.string(key, git_hash(run_git).unwrap_or_else(|| "unknown".to_string()))
+    }
+
+    /// Render the version information to a resource script declaring it with ID 1.
+    pub fn to_rc(&self) -> String {
+        let mut rc = String::new();
+        // Values may be non-ASCII; both RC.EXE and windres default to the ANSI codepage otherwise
+        rc.push_str("#pragma code_page(65001)\n");
+        let langid = match self.language {
+            Some(langid) => {
+                // LANGID is MAKELANGID(primary, sub), i.e. sub << 10 | primary
+                rc.push_str(&format!("LANGUAGE 0x{:x}, 0x{:x}\n", langid & 0x3FF, langid >> 10));
+                langid
+            }
+            None => 0x409,
+        };
+        rc.push_str("1 VERSIONINFO\n");
+        rc.push_str(&format!("FILEVERSION {}\n", version_list(self.file_version)));
+        rc.push_str(&format!("PRODUCTVERSION {}\n", version_list(self.product_version)));
+        rc.push_str("FILEFLAGSMASK 0x3fL\n");
+        // VS_FF_PRERELEASE
+        rc.push_str(&format!("FILEFLAGS 0x{:x}L\n", if self.prerelease { 0x2 } else { 0x0 }));
+        // VOS_NT_WINDOWS32, VFT_APP
+        rc.push_str("FILEOS 0x40004L\n");
+        rc.push_str("FILETYPE 0x1L\n");
+        rc.push_str("FILESUBTYPE 0x0L\n");
+        rc.push_str("BEGIN\n");
+        rc.push_str("    BLOCK \"StringFileInfo\"\n");
+        rc.push_str("    BEGIN\n");
+        rc.push_str(&format!("        BLOCK \"{:04x}04b0\"\n", langid));
+        rc.push_str("        BEGIN\n");
+        for (key, value) in &self.strings {
+            rc.push_str(&format!("            VALUE {}, {}\n", rc_quote(key), rc_quote(value)));
+        }
+        rc.push_str("        END\n");
+        rc.push_str("    END\n");
+        rc.push_str("    BLOCK \"VarFileInfo\"\n");
+        rc.push_str("    BEGIN\n");
+        rc.push_str(&format!("        VALUE \"Translation\", 0x{:x}, 1200\n", langid));
+        rc.push_str("    END\n");
+        rc.push_str("END\n");
+        rc
+    }
+}
+
+
+fn git_hash(run_git: bool) -> Option<String> {
+    if let Some(hash) = env::var(GIT_HASH_ENV_VAR).ok().filter(|h| !h.is_empty()) {
+        return Some(hash);
+    }
+    if !run_git {
+        return None;
+    }
+
+    let output = Command::new("git").args(&["rev-parse", "HEAD"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok().map(|h| h.trim().to_string()).filter(|h| !h.is_empty())
+}
+
+fn version_list(version: [u16; 4]) -> String {
+    format!("{},{},{},{}", version[0], version[1], version[2], version[3])
+}
+
+/// Quote a string for a resource script: backslashes are escaped and quotes are doubled.
+pub(crate) fn rc_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\"\""))
+}
@@ -1,21 +1,28 @@
 use std::sync::atomic::Ordering::SeqCst;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, AtomicPtr};
+use std::sync::Mutex;
 use std::path::{PathBuf, Path};
-use std::process::Command;
+#[cfg(feature = "vswhom")]
 use vswhom::VsFindResult;
+use super::{ScriptEncoding, DiscoveryStrategy, Options, Arch, debug_log, detect_arch, probed, lazy};
+use super::compile_commands::resolve_in_path;
+use super::toolchain::{windows_10_kits_bin_dirs, try_bin_dir, read_dir_logged, is_same_dir, dedup_dirs, rc_arguments};
+use std::ffi::OsString;
 use winreg::enums::*;
-use std::{env, fs};
+use std::{env, fs, ptr};
 use winreg;
 
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct ResourceCompiler;
+pub struct ResourceCompiler {
+    arch: Arch,
+}
 
 
 impl ResourceCompiler {
     #[inline(always)]
-    pub fn new() -> ResourceCompiler {
-        ResourceCompiler
+    pub fn new(target: &str) -> ResourceCompiler {
+        ResourceCompiler { arch: target_arch(target) }
     }
 
     #[inline(always)]
@@ -23,41 +30,186 @@ impl ResourceCompiler {
         true
     }
 
-    pub fn compile_resource(&self, out_dir: &str, prefix: &str, resource: &str) {
-        // `.res`es are linkable under MSVC as well as normal libraries.
-        if !Command::new(find_windows_sdk_tool_impl("rc.exe").as_ref().map_or(Path::new("rc.exe"), Path::new))
-            .args(&["/fo", &format!("{}/{}.lib", out_dir, prefix), resource])
-            .status()
-            .expect("Are you sure you have RC.EXE in your $PATH?")
-            .success() {
-            panic!("RC.EXE failed to compile specified resource file");
+    pub fn script_encoding(&self) -> ScriptEncoding {
+        ScriptEncoding::Utf16
+    }
+
+    pub fn compiler(&self, options: &Options) -> (PathBuf, DiscoveryStrategy) {
+        find_windows_sdk_tool_with("rc.exe", self.arch(options), !options.explicit_includes.unwrap_or(false))
+            .unwrap_or_else(|| (PathBuf::from("rc.exe"), DiscoveryStrategy::Path))
+    }
+
+    /// The MSVC compiler, if installed, to tell a missing Windows SDK from a discovery failure when `RC.EXE` isn't found:
+    /// in `%PATH%` in Developer Command Prompts, and otherwise next to the `LINK.EXE` vswhom finds
+    pub fn msvc_compiler(&self) -> Option<PathBuf> {
+        let cl = resolve_in_path(Path::new("cl.exe"));
+        if cl.is_file() { Some(cl) } else { find_vs_tool_with_vswhom("cl.exe") }
+    }
+
+    /// `llvm-rc` takes `RC.EXE`'s switches, to be tried if it fails;
+    /// `windres` doesn't, and reads neither the UTF-16 scripts generated for `RC.EXE` nor `%INCLUDE%`, so it isn't
+    pub fn alternative_compilers(&self, _: &Options) -> Vec<PathBuf> {
+        vec![PathBuf::from("llvm-rc")]
+    }
+
+    /// `.res`es are linkable under MSVC as well as normal libraries.
+    pub fn output_file(&self, out_dir: &str, prefix: &str) -> String {
+        format!("{}/{}.lib", out_dir, prefix)
+    }
+
+    pub fn object_file(&self, out_dir: &str, prefix: &str) -> String {
+        format!("{}/{}.obj", out_dir, prefix)
+    }
+
+    /// `RC.EXE` only makes `.res`es, which `CVTRES.EXE` converts to objects
+    #[inline(always)]
+    pub fn object_needs_res(&self) -> bool {
+        true
+    }
+
+    /// `LINK.EXE` doesn't read thin archives
+    pub fn thin_archive(&self, _: &Path, _: &str, _: &str) -> Option<(PathBuf, Vec<OsString>)> {
+        None
+    }
+
+    /// The extant component directories of the SDK headers with the specified version, or `None` if it's not installed
+    pub fn sdk_include_dirs(&self, version: &str) -> Option<Vec<PathBuf>> {
+        let version_dir = windows_kits_root("KitsRoot10").or_else(|| env::var_os("WindowsSdkDir").map(PathBuf::from))?.join("Include").join(version);
+        if !version_dir.is_dir() {
+            return None;
+        }
+        Some(["um", "shared", "ucrt", "winrt"].iter().map(|component| version_dir.join(component)).filter(|dir| dir.is_dir()).collect())
+    }
+
+    /// The target's, unless [overridden](struct.Options.html#method.arch)
+    fn arch(&self, options: &Options) -> Arch {
+        options.arch.unwrap_or(self.arch)
+    }
+
+    /// Where the SDK's `um` and `shared` headers, which `windows.h` comes from and needs, would be,
+    /// if neither `%INCLUDE%` nor the include directories have them
+    pub fn missing_sdk_headers(&self, options: &Options) -> Vec<PathBuf> {
+        let mut dirs = explicit_include_dirs();
+        dirs.extend(options.search_dirs().cloned());
+        if let Some(ref version) = options.sdk_include_version {
+            dirs.extend(self.sdk_include_dirs(version).unwrap_or_default());
+        }
+
+        let version = options.sdk_include_version
+            .clone()
+            .or_else(|| env::var("WindowsSDKVersion").ok().map(|v| v.trim_end_matches(&['\\', '/'][..]).to_string()).filter(|v| !v.is_empty()))
+            .unwrap_or_else(|| "<version>".to_string());
+        let include_root = windows_kits_root("KitsRoot10")
+            .or_else(|| env::var_os("WindowsSdkDir").map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from(r"C:\Program Files (x86)\Windows Kits\10"))
+            .join("Include")
+            .join(version);
+        [("um", "windows.h"), ("shared", "winapifamily.h")]
+            .iter()
+            .filter(|(_, header)| !dirs.iter().any(|dir| dir.join(header).is_file()))
+            .map(|(component, _)| include_root.join(component))
+            .collect()
+    }
+
+    pub fn arguments(&self, resource: &str, out_file: &str, options: &Options) -> Vec<OsString> {
+        let sdk_dirs = match options.sdk_include_version {
+            Some(ref version) => self.sdk_include_dirs(version).unwrap_or_default(),
+            None => vec![],
+        };
+        let explicit_dirs = if options.explicit_includes.unwrap_or(false) {
+            explicit_include_dirs()
+        } else {
+            vec![]
+        };
+        rc_arguments(resource, out_file, None, sdk_dirs.into_iter().chain(explicit_dirs), options)
+    }
+
+    pub fn res_arguments(&self, resource: &str, res_file: &str, options: &Options) -> Vec<OsString> {
+        self.arguments(resource, res_file, options)
+    }
+
+    /// The output is a `.res` already, from `RC.EXE` or `llvm-rc` alike, so it's just copied, unless it's to be an object
+    pub fn res_to_output(&self, _: &Path, res_file: &str, out_file: &str, options: &Options) -> Option<(PathBuf, Vec<OsString>)> {
+        if !options.object.unwrap_or(false) {
+            return None;
         }
+        let machine = match self.arch(options) {
+            Arch::X86 => "X86",
+            Arch::X64 => "X64",
+            Arch::Arm => "ARM",
+            Arch::Arm64 => "ARM64",
+        };
+        Some((find_cvtres(), vec!["/nologo".into(), format!("/machine:{}", machine).into(), format!("/out:{}", out_file).into(), res_file.into()]))
     }
 }
 
 
-#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
-enum Arch {
-    X86,
-    X64,
+/// Windows hosts don't need `TARGET`, so default to x86, as before ARM support
+fn target_arch(target: &str) -> Arch {
+    detect_arch(target).unwrap_or(Arch::X86)
 }
 
 pub fn find_windows_sdk_tool_impl(tool: &str) -> Option<PathBuf> {
-    let arch = if env::var("TARGET").expect("No TARGET env var").starts_with("x86_64") {
-        Arch::X64
-    } else {
-        Arch::X86
-    };
+    find_sdk_tool_impl(tool, &env::var("TARGET").expect("No TARGET env var"))
+}
+
+pub fn find_sdk_tool_impl(tool: &str, target: &str) -> Option<PathBuf> {
+    find_windows_sdk_tool_with(tool, target_arch(target), true).map(|(tool, _)| tool)
+}
+
+/// The tool, architecture, and whether %INCLUDE% was updated, and what was found for them
+type FoundTool = (String, Arch, bool, Option<(PathBuf, DiscoveryStrategy)>);
+
+/// Memoized per process, since discovery goes through the registry and, possibly, vswhom, for every resource compiled
+fn find_windows_sdk_tool_with(tool: &str, arch: Arch, update_include: bool) -> Option<(PathBuf, DiscoveryStrategy)> {
+    static FOUND: AtomicPtr<Mutex<Vec<FoundTool>>> = AtomicPtr::new(ptr::null_mut());
+
+    let mut found = lazy(&FOUND, || Mutex::new(vec![])).lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, _, _, ret)) = found.iter().find(|(t, a, u, _)| t == tool && *a == arch && *u == update_include) {
+        return ret.clone();
+    }
+    let ret = find_windows_sdk_tool_uncached(tool, arch, update_include);
+    found.push((tool.to_string(), arch, update_include, ret.clone()));
+    ret
+}
 
-    find_windows_kits_tool("KitsRoot10", arch, tool)
-        .or_else(|| find_windows_kits_tool("KitsRoot81", arch, tool))
-        .or_else(|| find_windows_kits_tool("KitsRoot", arch, tool))
-        .or_else(|| find_latest_windows_sdk_tool(arch, tool))
-        .or_else(|| find_windows_10_kits_tool("KitsRoot10", arch, tool))
-        .or_else(|| find_with_vswhom(arch, tool))
+fn find_windows_sdk_tool_uncached(tool: &str, arch: Arch, update_include: bool) -> Option<(PathBuf, DiscoveryStrategy)> {
+    let found = |strategy: DiscoveryStrategy| move |tool: PathBuf| (tool, strategy);
+    find_developer_prompt_tool(arch, tool)
+        .map(found(DiscoveryStrategy::DeveloperPrompt))
+        .or_else(|| {
+            windows_kits_roots(&["KitsRoot10", "KitsRoot81", "KitsRoot"])
+                .into_iter()
+                .filter_map(|root| find_windows_kits_tool(root, arch, tool))
+                .next()
+                .map(found(DiscoveryStrategy::WindowsKits))
+        })
+        .or_else(|| find_latest_windows_sdk_tool(arch, tool).map(found(DiscoveryStrategy::WindowsSdk)))
+        .or_else(|| find_windows_10_kits_tool("KitsRoot10", arch, tool, update_include).map(found(DiscoveryStrategy::Windows10Kits)))
+        .or_else(|| find_with_vswhom(arch, tool).map(found(DiscoveryStrategy::Vswhom)))
 }
 
 
+/// `CVTRES.EXE` comes with Visual Studio, not the Windows SDK,
+/// so it's in `%PATH%` in Developer Command Prompts, and otherwise next to the `LINK.EXE` vswhom finds
+fn find_cvtres() -> PathBuf {
+    find_vs_tool_with_vswhom("cvtres.exe").unwrap_or_else(|| PathBuf::from("cvtres.exe"))
+}
+
+#[cfg(feature = "vswhom")]
+fn find_vs_tool_with_vswhom(tool: &str) -> Option<PathBuf> {
+    if env::var_os("VCINSTALLDIR").is_some() {
+        return None;
+    }
+    VsFindResult::search().and_then(|res| res.vs_exe_path).and_then(|dir| try_tool(DiscoveryStrategy::Vswhom, PathBuf::from(dir), tool))
+}
+
+#[cfg(not(feature = "vswhom"))]
+fn find_vs_tool_with_vswhom(_: &str) -> Option<PathBuf> {
+    None
+}
+
+#[cfg(feature = "vswhom")]
 fn find_with_vswhom(arch: Arch, tool: &str) -> Option<PathBuf> {
     let res = VsFindResult::search();
     res.as_ref()
@@ -71,7 +223,7 @@ fn find_with_vswhom(arch: Arch, tool: &str) -> Option<PathBuf> {
             root.push(ver);
             try_bin_dir(root, "x86", "x64", arch)
         })
-        .and_then(|pb| try_tool(pb, tool))
+        .and_then(|pb| try_tool(DiscoveryStrategy::Vswhom, pb, tool))
         .or_else(move || {
             res.and_then(|res| res.windows_sdk_root)
                 .map(PathBuf::from)
@@ -80,18 +232,41 @@ fn find_with_vswhom(arch: Arch, tool: &str) -> Option<PathBuf> {
                     root.pop();
                     try_bin_dir(root, "bin/x86", "bin/x64", arch)
                 })
-                .and_then(|pb| try_tool(pb, tool))
+                .and_then(|pb| try_tool(DiscoveryStrategy::Vswhom, pb, tool))
+        })
+}
+
+#[cfg(not(feature = "vswhom"))]
+fn find_with_vswhom(_: Arch, _: &str) -> Option<PathBuf> {
+    None
+}
+
+/// Visual Studio Developer Command Prompts set the SDK to use, with %INCLUDE% already to match
+fn find_developer_prompt_tool(arch: Arch, tool: &str) -> Option<PathBuf> {
+    env::var_os("WindowsSdkVerBinPath")
+        .and_then(|bin_dir| try_bin_dir(bin_dir, "x86", "x64", arch))
+        .and_then(|pb| try_tool(DiscoveryStrategy::DeveloperPrompt, pb, tool))
+        .or_else(|| {
+            let sdk_root = PathBuf::from(env::var_os("WindowsSdkDir")?);
+            sdk_version_bin_dir(&sdk_root.join("bin"), arch).and_then(|pb| try_tool(DiscoveryStrategy::DeveloperPrompt, pb, tool)).or_else(|| {
+                windows_10_kits_bin_dirs(&sdk_root.join("bin"), arch)
+                    .into_iter()
+                    .chain(try_bin_dir(sdk_root, "bin/x86", "bin/x64", arch))
+                    .filter_map(|pb| try_tool(DiscoveryStrategy::DeveloperPrompt, pb, tool))
+                    .next()
+            })
         })
 }
 
 // Windows 8 - 10
-fn find_windows_kits_tool(key: &str, arch: Arch, tool: &str) -> Option<PathBuf> {
-    winreg::RegKey::predef(HKEY_LOCAL_MACHINE)
-        .open_subkey_with_flags(r"SOFTWARE\Microsoft\Windows Kits\Installed Roots", KEY_QUERY_VALUE)
-        .and_then(|reg_key| reg_key.get_value::<String, _>(key))
-        .ok()
-        .and_then(|root_dir| try_bin_dir(root_dir, "bin/x86", "bin/x64", arch))
-        .and_then(|pb| try_tool(pb, tool))
+fn find_windows_kits_tool(root_dir: PathBuf, arch: Arch, tool: &str) -> Option<PathBuf> {
+    try_bin_dir(root_dir, "bin/x86", "bin/x64", arch).and_then(|pb| try_tool(DiscoveryStrategy::WindowsKits, pb, tool))
+}
+
+/// The roots in the specified registry values, in order, without duplicates,
+/// since some installs point, say, both `KitsRoot10` and `KitsRoot81` at the same directory
+fn windows_kits_roots(keys: &[&str]) -> Vec<PathBuf> {
+    dedup_dirs(keys.iter().filter_map(|key| windows_kits_root(key)))
 }
 
 // Windows Vista - 7
@@ -101,38 +276,49 @@ fn find_latest_windows_sdk_tool(arch: Arch, tool: &str) -> Option<PathBuf> {
         .and_then(|reg_key| reg_key.get_value::<String, _>("CurrentInstallFolder"))
         .ok()
         .and_then(|root_dir| try_bin_dir(root_dir, "Bin", "Bin/x64", arch))
-        .and_then(|pb| try_tool(pb, tool))
+        .and_then(|pb| try_tool(DiscoveryStrategy::WindowsSdk, pb, tool))
 }
 
 // Windows 10 with subdir support
-fn find_windows_10_kits_tool(key: &str, arch: Arch, tool: &str) -> Option<PathBuf> {
-    let kit_root = (winreg::RegKey::predef(HKEY_LOCAL_MACHINE)
-        .open_subkey_with_flags(r"SOFTWARE\Microsoft\Windows Kits\Installed Roots", KEY_QUERY_VALUE)
-        .and_then(|reg_key| reg_key.get_value::<String, _>(key))
-        .ok())?;
-    include_windows_10_kits(&kit_root);
-    let root_dir = kit_root + "/bin";
-
-    for entry in fs::read_dir(&root_dir).ok()?.filter(|d| d.is_ok()).map(Result::unwrap) {
-        let fname = entry.file_name().into_string();
-        let ftype = entry.file_type();
-        if fname.is_err() || ftype.is_err() || ftype.unwrap().is_file() {
-            continue;
-        }
+fn find_windows_10_kits_tool(key: &str, arch: Arch, tool: &str, update_include: bool) -> Option<PathBuf> {
+    let kit_root = windows_kits_root(key)?;
+    if update_include {
+        include_windows_10_kits(&kit_root);
+    }
 
-        let fname = entry.file_name().into_string().unwrap();
-        if let Some(rc) = try_bin_dir(root_dir.clone(), &format!("{}/x86", fname), &format!("{}/x64", fname), arch).and_then(|pb| try_tool(pb, tool)) {
-            return Some(rc);
-        }
+    sdk_version_bin_dir(&kit_root.join("bin"), arch).and_then(|pb| try_tool(DiscoveryStrategy::Windows10Kits, pb, tool)).or_else(|| {
+        windows_10_kits_bin_dirs(&kit_root.join("bin"), arch).into_iter().filter_map(|pb| try_tool(DiscoveryStrategy::Windows10Kits, pb, tool)).next()
+    })
+}
+
+/// The \bin\<version>\<arch>\ folder for `%WindowsSDKVersion%`, set by Developer Command Prompts and some CI images to select the SDK,
+/// which is checked before, and saves, listing all versions
+fn sdk_version_bin_dir(root_dir: &Path, arch: Arch) -> Option<PathBuf> {
+    let version = env::var("WindowsSDKVersion").ok()?;
+    // Conventionally 10.0.22621.0\
+    let version = version.trim_end_matches(&['\\', '/'][..]);
+    if version.is_empty() {
+        return None;
     }
+    try_bin_dir(root_dir.join(version), "x86", "x64", arch)
+}
 
-    None
+/// The registry value may or may not end with a path separator, so it must only ever be `join()`ed onto
+fn windows_kits_root(key: &str) -> Option<PathBuf> {
+    winreg::RegKey::predef(HKEY_LOCAL_MACHINE)
+        .open_subkey_with_flags(r"SOFTWARE\Microsoft\Windows Kits\Installed Roots", KEY_QUERY_VALUE)
+        .and_then(|reg_key| reg_key.get_value::<String, _>(key))
+        .ok()
+        .map(PathBuf::from)
 }
 
+const VAR_INCLUDE: &str = "INCLUDE";
+
 /// Update %INCLUDE% to contain all \Include\<version>\ folders before invoking rc.exe
 /// (https://github.com/nabijaczleweli/rust-embed-resource/pull/17),
-/// fixing "Unable to find windows.h" errors (https://github.com/nabijaczleweli/rust-embed-resource/issues/11)
-fn include_windows_10_kits(kit_root: &str) {
+/// fixing "Unable to find windows.h" errors (https://github.com/nabijaczleweli/rust-embed-resource/issues/11),
+/// unless it can find windows.h already
+fn include_windows_10_kits(kit_root: &Path) {
     static IS_INCLUDED: AtomicBool = AtomicBool::new(false);
 
     if !IS_INCLUDED.swap(true, SeqCst) {
@@ -140,50 +326,97 @@ fn include_windows_10_kits(kit_root: &str) {
     }
 }
 
-fn include_windows_10_kits_impl(kit_root: &str) {
-    const VAR_INCLUDE: &str = "INCLUDE";
+fn include_windows_10_kits_impl(kit_root: &Path) {
+    // Developer Command Prompts and the like have it set up already, so leave it be
+    if let Some(dir) = env::var_os(VAR_INCLUDE).and_then(|include| env::split_paths(&include).find(|dir| dir.join("windows.h").is_file())) {
+        debug_log(format_args!("windows.h found in %INCLUDE% ({}), leaving it as-is", dir.display()));
+        return;
+    }
 
-    if let Ok(include_root) = fs::read_dir(kit_root.to_string() + r"\Include\") {
-        let mut include = env::var(VAR_INCLUDE).unwrap_or_default();
-        if !include.ends_with(';') {
-            include.push(';');
-        }
+    let sub_dirs = windows_10_kits_include_dirs(kit_root);
+    if sub_dirs.is_empty() {
+        return;
+    }
 
-        get_dirs(include_root).filter_map(|dir| fs::read_dir(dir.path()).ok()).for_each(|dir| {
-            get_dirs(dir).for_each(|sub_dir| if let Some(sub_dir) = sub_dir.path().to_str() {
-                if !include.contains(sub_dir) {
-                    include.push_str(sub_dir);
-                    include.push(';');
-                }
-            })
-        });
+    let mut include = env::var(VAR_INCLUDE).unwrap_or_default();
+    if !include.ends_with(';') {
+        include.push(';');
+    }
 
-        env::set_var(VAR_INCLUDE, include);
+    for sub_dir in sub_dirs {
+        if let Some(sub_dir) = sub_dir.to_str() {
+            if !include.split(';').any(|dir| is_same_dir(Path::new(dir), Path::new(sub_dir))) {
+                include.push_str(sub_dir);
+                include.push(';');
+            }
+        }
     }
-}
 
-fn get_dirs(read_dir: fs::ReadDir) -> impl Iterator<Item = fs::DirEntry> {
-    read_dir.filter_map(|dir| dir.ok()).filter(|dir| dir.file_type().map(|ft| ft.is_dir()).unwrap_or(false))
+    env::set_var(VAR_INCLUDE, include);
 }
 
-fn try_bin_dir<R: Into<PathBuf>>(root_dir: R, x86_bin: &str, x64_bin: &str, arch: Arch) -> Option<PathBuf> {
-    try_bin_dir_impl(root_dir.into(), x86_bin, x64_bin, arch)
+/// All \Include\<version>\<component>\ folders
+fn windows_10_kits_include_dirs(kit_root: &Path) -> Vec<PathBuf> {
+    match read_dir_logged(&kit_root.join("Include")) {
+        Some(include_root) => {
+            get_dirs(include_root)
+                .filter_map(|dir| read_dir_logged(&dir.path()))
+                .flat_map(|dir| get_dirs(dir).map(|sub_dir| sub_dir.path()))
+                .collect()
+        }
+        None => vec![],
+    }
 }
 
-fn try_bin_dir_impl(mut root_dir: PathBuf, x86_bin: &str, x64_bin: &str, arch: Arch) -> Option<PathBuf> {
-    match arch {
-        Arch::X86 => root_dir.push(x86_bin),
-        Arch::X64 => root_dir.push(x64_bin),
+/// %INCLUDE% and the Windows 10 kits' include folders, deduplicated and filtered to extant directories,
+/// to be passed as explicit `/i` arguments instead of updating %INCLUDE%
+fn explicit_include_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = env::var_os(VAR_INCLUDE).map(|include| env::split_paths(&include).collect()).unwrap_or_default();
+    if let Some(kit_root) = windows_kits_root("KitsRoot10") {
+        dirs.extend(windows_10_kits_include_dirs(&kit_root));
     }
 
-    if root_dir.is_dir() {
-        Some(root_dir)
-    } else {
-        None
+    let mut ret: Vec<PathBuf> = vec![];
+    for dir in dirs {
+        if dir.is_dir() && !ret.iter().any(|r| is_same_dir(r, &dir)) {
+            ret.push(dir);
+        }
     }
+    ret
 }
 
-fn try_tool(mut pb: PathBuf, tool: &str) -> Option<PathBuf> {
+fn get_dirs(read_dir: fs::ReadDir) -> impl Iterator<Item = fs::DirEntry> {
+    read_dir.filter_map(|dir| dir.ok()).filter(|dir| dir.file_type().map(|ft| ft.is_dir()).unwrap_or(false))
+}
+
+fn try_tool(strategy: DiscoveryStrategy, mut pb: PathBuf, tool: &str) -> Option<PathBuf> {
     pb.push(tool);
-    if pb.exists() { Some(pb) } else { None }
+    let exists = pb.exists();
+    probed(strategy, &pb, exists);
+    if exists { Some(pb) } else { None }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Arch, try_bin_dir, windows_10_kits_include_dirs};
+    use std::path::{MAIN_SEPARATOR, PathBuf};
+    use std::{env, fs};
+
+    #[test]
+    fn kits_root_with_and_without_trailing_separator() {
+        let root = env::temp_dir().join("embed-resource-test-trailing-separator");
+        let bin = root.join("bin").join("10.0.22621.0").join("x64");
+        let include = root.join("Include").join("10.0.22621.0").join("um");
+        fs::create_dir_all(&bin).unwrap();
+        fs::create_dir_all(&include).unwrap();
+
+        // As the KitsRoot10 registry value may be
+        for kit_root in &[root.clone(), PathBuf::from(format!("{}{}", root.display(), MAIN_SEPARATOR))] {
+            assert_eq!(try_bin_dir(kit_root.join("bin").join("10.0.22621.0"), "x86", "x64", Arch::X64), Some(bin.clone()));
+            assert_eq!(windows_10_kits_include_dirs(kit_root), vec![include.clone()]);
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
 }
@@ -1,5 +1,7 @@
-use std::process::Command;
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{PathBuf, Path};
+use super::{ScriptEncoding, DiscoveryStrategy, Options, ar_for_windres, windres_for_linker};
+use super::toolchain::windres_arguments;
 
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -8,7 +10,7 @@ pub struct ResourceCompiler;
 
 impl ResourceCompiler {
     #[inline(always)]
-    pub fn new() -> ResourceCompiler {
+    pub fn new(_: &str) -> ResourceCompiler {
         ResourceCompiler
     }
 
@@ -17,17 +19,76 @@ impl ResourceCompiler {
         true
     }
 
-    pub fn compile_resource(&self, out_dir: &str, prefix: &str, resource: &str) {
-        let out_file = format!("{}/lib{}.a", out_dir, prefix);
-        match Command::new("windres").args(&["--input", resource, "--output-format=coff", "--output", &out_file]).status() {
-            Ok(stat) if stat.success() => {}
-            Ok(stat) => panic!("windres failed to compile \"{}\" into \"{}\" with {}", resource, out_file, stat),
-            Err(e) => panic!("Couldn't to execute windres to compile \"{}\" into \"{}\": {}", resource, out_file, e),
+    pub fn script_encoding(&self) -> ScriptEncoding {
+        ScriptEncoding::Utf8
+    }
+
+    pub fn compiler(&self, options: &Options) -> (PathBuf, DiscoveryStrategy) {
+        match options.linker.as_ref().and_then(|linker| windres_for_linker(linker)) {
+            Some(windres) => (windres, DiscoveryStrategy::Linker),
+            None => (PathBuf::from("windres"), DiscoveryStrategy::Path),
         }
     }
+
+    /// MinGW's `windres` doesn't come with a Windows SDK
+    pub fn msvc_compiler(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// `llvm-windres` takes `windres`' switches, to be tried if it fails
+    pub fn alternative_compilers(&self, _: &Options) -> Vec<PathBuf> {
+        vec![PathBuf::from("llvm-windres")]
+    }
+
+    pub fn output_file(&self, out_dir: &str, prefix: &str) -> String {
+        format!("{}/lib{}.a", out_dir, prefix)
+    }
+
+    /// `windres`' COFF output is an object already
+    pub fn object_file(&self, out_dir: &str, prefix: &str) -> String {
+        format!("{}/{}.o", out_dir, prefix)
+    }
+
+    #[inline(always)]
+    pub fn object_needs_res(&self) -> bool {
+        false
+    }
+
+    /// `ar` from the same binutils as the `windres` that compiled the object, with `T` making a thin archive,
+    /// referencing the object instead of containing it
+    pub fn thin_archive(&self, compiler: &Path, object: &str, archive: &str) -> Option<(PathBuf, Vec<OsString>)> {
+        Some((ar_for_windres(compiler), vec!["rcsT".into(), archive.into(), object.into()]))
+    }
+
+    /// MinGW brings its own headers
+    pub fn sdk_include_dirs(&self, _: &str) -> Option<Vec<PathBuf>> {
+        Some(vec![])
+    }
+
+    pub fn missing_sdk_headers(&self, _: &Options) -> Vec<PathBuf> {
+        vec![]
+    }
+
+    pub fn arguments(&self, resource: &str, out_file: &str, options: &Options) -> Vec<OsString> {
+        windres_arguments(resource, "--output-format=coff", out_file, options)
+    }
+
+    pub fn res_arguments(&self, resource: &str, res_file: &str, options: &Options) -> Vec<OsString> {
+        windres_arguments(resource, "--output-format=res", res_file, options)
+    }
+
+    /// The `windres` that compiled the `.res` converts it to a COFF object without recompiling
+    pub fn res_to_output(&self, compiler: &Path, res_file: &str, out_file: &str, _: &Options) -> Option<(PathBuf, Vec<OsString>)> {
+        Some((compiler.to_path_buf(),
+              vec!["--input".into(), res_file.into(), "--input-format=res".into(), "--output-format=coff".into(), "--output".into(), out_file.into()]))
+    }
 }
 
 
 pub fn find_windows_sdk_tool_impl(_: &str) -> Option<PathBuf> {
     None
 }
+
+pub fn find_sdk_tool_impl(_: &str, _: &str) -> Option<PathBuf> {
+    None
+}